@@ -0,0 +1,142 @@
+// Criterion benchmarks, which unlike the #[bench] ones behind the
+// nightly feature run on a stable toolchain
+use criterion::{
+	black_box, criterion_group, criterion_main, BenchmarkId,
+	Criterion,
+};
+use kings_walk::{
+	generate_hard, random_puzzle, seed_rng, SolverBuilder, State,
+};
+
+// A puzzle of side n with a quarter of its cells blank, the same one
+// for every run
+fn puzzle(n: usize) -> State {
+	seed_rng(n as u64);
+	random_puzzle(n, n * n / 4).unwrap()
+}
+
+fn solve(c: &mut Criterion) {
+	let mut group = c.benchmark_group("solve");
+	for &n in &[4, 6, 8] {
+		let mut state = puzzle(n);
+		state.random_start();
+		group.bench_with_input(
+			BenchmarkId::new("score", n),
+			&state,
+			|b, state| b.iter(|| black_box(state).score()),
+		);
+		group.bench_with_input(
+			BenchmarkId::new("step", n),
+			&state,
+			|b, state| {
+				b.iter(|| {
+					let mut state = state.clone();
+					let score = state.score();
+					state.step(score)
+				})
+			},
+		);
+		let state = puzzle(n);
+		group.bench_with_input(
+			BenchmarkId::new("hillclimb", n),
+			&state,
+			|b, state| {
+				seed_rng(n as u64);
+				b.iter(|| {
+					let mut state = state.clone();
+					assert!(state.hillclimb());
+				})
+			},
+		);
+	}
+	// a board with no free cells has nothing to climb
+	let fixed =
+		State::new(vec![4, 5, 1, 3, 2, 6, 9, 8, 7], 3).unwrap();
+	group.bench_function("hillclimb_fixed", |b| {
+		b.iter(|| black_box(fixed.clone()).hillclimb())
+	});
+	group.finish();
+}
+
+// Hillclimb a hard board with and without a cap on the steps of each
+// climb, counting how long a fixed number of restarts takes
+fn plateau(c: &mut Criterion) {
+	let mut group = c.benchmark_group("plateau");
+	let hard = generate_hard(5, 4, 1);
+	for &limit in &[None, Some(4), Some(16)] {
+		let name =
+			limit.map_or("none".to_string(), |l| l.to_string());
+		let mut solver = SolverBuilder::new().max_restarts(100);
+		if let Some(limit) = limit {
+			solver = solver.plateau_limit(limit);
+		}
+		group.bench_function(
+			BenchmarkId::new("restarts", name),
+			|b| {
+				seed_rng(6);
+				b.iter(|| {
+					let mut state = hard.clone();
+					solver.solve(&mut state)
+				})
+			},
+		);
+	}
+	group.finish();
+}
+
+// Solve the same puzzles by plain hillclimbing and by growing the
+// longest run first, timing both after reporting the steps (climbing
+// steps and restarts) each takes on average over a few seeds
+fn extend_run(c: &mut Criterion) {
+	let mut group = c.benchmark_group("extend_run");
+	for &n in &[4, 6] {
+		let state = puzzle(n);
+		let (mut plain, mut extended) = (0, 0);
+		for seed in 0..10 {
+			seed_rng(seed);
+			let (solved, trace) =
+				state.clone().hillclimb_trace(usize::MAX);
+			assert!(solved);
+			plain += trace.last().unwrap().0;
+			seed_rng(seed);
+			let (solved, trace) =
+				state.clone().hillclimb_extend_run_trace(usize::MAX);
+			assert!(solved);
+			extended += trace.last().unwrap().0;
+		}
+		let runs = 10.0;
+		println!(
+			"extend_run/{}: hillclimb {:.1} steps, \
+			 hillclimb_extend_run {:.1} steps",
+			n,
+			plain as f64 / runs,
+			extended as f64 / runs
+		);
+		group.bench_with_input(
+			BenchmarkId::new("hillclimb", n),
+			&state,
+			|b, state| {
+				seed_rng(n as u64);
+				b.iter(|| {
+					let mut state = state.clone();
+					assert!(state.hillclimb());
+				})
+			},
+		);
+		group.bench_with_input(
+			BenchmarkId::new("hillclimb_extend_run", n),
+			&state,
+			|b, state| {
+				seed_rng(n as u64);
+				b.iter(|| {
+					let mut state = state.clone();
+					assert!(state.hillclimb_extend_run(usize::MAX));
+				})
+			},
+		);
+	}
+	group.finish();
+}
+
+criterion_group!(benches, solve, plateau, extend_run);
+criterion_main!(benches);
@@ -0,0 +1,30 @@
+// Print a random puzzle in the .kw format solve_file reads
+//
+// cargo run --example generate -- <n> [blanks] [seed]
+use kings_walk::{random_puzzle, seed_rng};
+use std::{env, process};
+
+fn main() {
+	let args: Vec<usize> = env::args()
+		.skip(1)
+		.map(|arg| {
+			arg.parse().unwrap_or_else(|_| {
+				eprintln!("usage: generate <n> [blanks] [seed]");
+				process::exit(2);
+			})
+		})
+		.collect();
+	let n = args.first().copied().unwrap_or(4);
+	// blank half of the board unless told otherwise
+	let blanks = args.get(1).copied().unwrap_or(n * n / 2);
+	if let Some(&seed) = args.get(2) {
+		seed_rng(seed as u64);
+	}
+	match random_puzzle(n, blanks) {
+		Ok(puzzle) => print!("{}", puzzle.to_puzzle_string()),
+		Err(e) => {
+			eprintln!("{}", e);
+			process::exit(2);
+		}
+	}
+}
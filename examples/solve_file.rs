@@ -0,0 +1,29 @@
+// Solve a puzzle read from a .kw file and print the solution
+//
+// cargo run --example solve_file -- examples/n_eq_3.kw
+use kings_walk::State;
+use std::{env, fs, process};
+
+fn main() {
+	let path = match env::args().nth(1) {
+		Some(path) => path,
+		None => {
+			eprintln!("usage: solve_file <puzzle.kw>");
+			process::exit(2);
+		}
+	};
+	let text = fs::read_to_string(&path).unwrap_or_else(|e| {
+		eprintln!("{}: {}", path, e);
+		process::exit(2);
+	});
+	let mut state: State = text.parse().unwrap_or_else(|e| {
+		eprintln!("{}: {}", path, e);
+		process::exit(2);
+	});
+	let solved = state.auto_solve();
+	print!("{}", state);
+	if !solved {
+		eprintln!("no solution found");
+		process::exit(1);
+	}
+}
@@ -0,0 +1,11 @@
+#![no_main]
+use kings_walk::State;
+use libfuzzer_sys::fuzz_target;
+
+// The parser must turn any input into a State or an error, never a
+// panic.
+fuzz_target!(|data: &[u8]| {
+	if let Ok(text) = std::str::from_utf8(data) {
+		let _ = text.parse::<State>();
+	}
+});
@@ -0,0 +1,282 @@
+use super::{State, RNG};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+// Temperatures the annealing schedule cools between
+const START_TEMPERATURE: f64 = 2.0;
+const END_TEMPERATURE: f64 = 0.05;
+// The share of worsening swaps suggest_initial_temp aims to accept
+const ACCEPT_WORSE: f64 = 0.8;
+
+impl State {
+	// Simulated annealing: try random swaps of two free cells,
+	// always keeping improvements and keeping worse boards with a
+	// probability that shrinks as the temperature cools. Runs for at
+	// most iterations swaps, leaves the best board found and returns
+	// whether it solved. The best board is kept even when it is not a
+	// solution, so unsolvable puzzles still get a best effort.
+	pub fn anneal(&mut self, iterations: usize) -> bool {
+		self.anneal_from(iterations, START_TEMPERATURE)
+	}
+	// anneal, cooling from start_temperature instead. 0.0 asks
+	// suggest_initial_temp for one, from 100 sampled swaps.
+	pub fn anneal_from(
+		&mut self,
+		iterations: usize,
+		start_temperature: f64,
+	) -> bool {
+		let start_temperature = if start_temperature == 0.0 {
+			let seed =
+				RNG.with(|rng_cell| rng_cell.borrow_mut().gen());
+			self.suggest_initial_temp(100, seed)
+		} else {
+			start_temperature
+		};
+		self.anneal_by(iterations, start_temperature, &|state| {
+			state.score() as f64
+		})
+	}
+	// A starting temperature for annealing from this board: the one
+	// at which ACCEPT_WORSE of the worsening swaps among samples
+	// random swaps of two free cells would be kept, on average. The
+	// swaps are drawn from a generator of its own seeded by seed.
+	// When no sampled swap makes the board worse, the usual starting
+	// temperature.
+	pub fn suggest_initial_temp(
+		&self,
+		samples: usize,
+		seed: u64,
+	) -> f64 {
+		let free = self.assignments.len();
+		if free < 2 {
+			return START_TEMPERATURE;
+		}
+		let mut rng = StdRng::seed_from_u64(seed);
+		let mut state = self.clone();
+		let score = state.score() as f64;
+		let losses: Vec<f64> = (0..samples)
+			.filter_map(|_| {
+				let idx1 = state.assignments[rng.gen_range(0, free)];
+				let idx2 = state.assignments[rng.gen_range(0, free)];
+				state.swap(idx1, idx2);
+				let delta = state.score() as f64 - score;
+				state.swap(idx1, idx2);
+				Some(-delta).filter(|&loss| loss > 0.0)
+			})
+			.collect();
+		temperature_for(&losses)
+	}
+	// anneal, but maximizing weighted_score instead of score. Still
+	// returns whether the board it leaves is solved.
+	pub fn anneal_weighted(
+		&mut self,
+		iterations: usize,
+		weights: &[f64],
+	) -> bool {
+		self.anneal_by(iterations, START_TEMPERATURE, &|state| {
+			state.weighted_score(weights)
+		})
+	}
+	// The score with the link from v to v+1 worth weights[v-1], or 1
+	// past the end of weights, instead of 1. Constraints are still
+	// worth 1 each.
+	pub fn weighted_score(&self, weights: &[f64]) -> f64 {
+		let links: f64 = (1..self.board.len())
+			.filter(|&v| {
+				self.unbroken(v, v + 1)
+					&& self
+						.king_distance(self.pos[v], self.pos[v + 1])
+						== 1
+			})
+			.map(|v| weights.get(v - 1).copied().unwrap_or(1.0))
+			.sum();
+		links + self.satisfied_constraints() as f64
+	}
+	// Anneal maximizing objective. The best board is tracked with
+	// total_cmp and a swap whose change in objective isn't a number
+	// is rejected, so a NaN or infinite objective can't panic or
+	// derail the search.
+	fn anneal_by(
+		&mut self,
+		iterations: usize,
+		start_temperature: f64,
+		objective: &dyn Fn(&State) -> f64,
+	) -> bool {
+		let free = self.assignments.len();
+		if free < 2 || iterations == 0 || self.is_solved() {
+			return self.is_solved();
+		}
+		let mut value = objective(self);
+		let mut best = (value, self.board.clone());
+		// geometric cooling from start_temperature to END_TEMPERATURE
+		let cooling = (END_TEMPERATURE / start_temperature)
+			.powf(1.0 / iterations as f64);
+		let mut temperature = start_temperature;
+		RNG.with(|rng_cell| {
+			let mut rng = rng_cell.borrow_mut();
+			for _ in 0..iterations {
+				let idx1 = self.assignments[rng.gen_range(0, free)];
+				let idx2 = self.assignments[rng.gen_range(0, free)];
+				if !self.swap_allowed(idx1, idx2) {
+					temperature *= cooling;
+					continue;
+				}
+				self.swap(idx1, idx2);
+				let new_value = objective(self);
+				let delta = new_value - value;
+				if delta >= 0.0
+					|| rng.gen::<f64>() < (delta / temperature).exp()
+				{
+					value = new_value;
+					if value.total_cmp(&best.0).is_gt() {
+						best = (value, self.board.clone());
+						if self.is_solved() {
+							break;
+						}
+					}
+				} else {
+					// undo the rejected swap
+					self.swap(idx1, idx2);
+				}
+				temperature *= cooling;
+			}
+		});
+		self.set_board(best.1);
+		self.is_solved()
+	}
+}
+
+// The temperature at which a swap losing the average of losses is
+// kept with chance ACCEPT_WORSE, or the usual starting temperature
+// when there are no losses
+fn temperature_for(losses: &[f64]) -> f64 {
+	if losses.is_empty() {
+		return START_TEMPERATURE;
+	}
+	let average = losses.iter().sum::<f64>() / losses.len() as f64;
+	-average / ACCEPT_WORSE.ln()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn anneal_should_solve_n_eq_4() {
+		let mut state = State::new(
+			vec![0, 0, 0, 0, 0, 3, 4, 0, 0, 0, 0, 0, 12, 0, 0, 0],
+			4,
+		)
+		.unwrap();
+		// annealing is not guaranteed to solve in one run
+		let solved = (0..100).any(|_| state.anneal(10_000));
+		assert!(solved);
+		assert_eq!(state.score(), state.max_score());
+	}
+	#[test]
+	fn anneal_should_keep_the_best_board() {
+		// 1 and 2 are fixed in opposite corners
+		let mut state =
+			State::new(vec![1, 0, 0, 0, 0, 0, 0, 0, 2], 3).unwrap();
+		// every link but the one between them can be made
+		assert!(!state.anneal(10_000));
+		assert_eq!(state.score(), state.max_score() - 1);
+		assert_eq!(state.board[0], 1);
+		assert_eq!(state.board[8], 2);
+	}
+	#[test]
+	fn suggest_initial_temp_should_scale_with_the_losses() {
+		let mut state = State::new_empty(5).unwrap();
+		assert!(state.hillclimb());
+		let temperature = state.suggest_initial_temp(100, 1);
+		assert!(temperature > 0.0);
+		assert_eq!(state.suggest_initial_temp(100, 1), temperature);
+		// an average loss kept with chance ACCEPT_WORSE
+		let kept = (-2.0 / temperature_for(&[1.0, 3.0])).exp();
+		assert!((kept - ACCEPT_WORSE).abs() < 1e-12);
+		assert_eq!(
+			temperature_for(&[2.0, 6.0]),
+			2.0 * temperature_for(&[1.0, 3.0])
+		);
+		assert_eq!(temperature_for(&[]), START_TEMPERATURE);
+		// two free cells in a walk, whose swap breaks one link in the
+		// first and two in the second
+		let walk = vec![1, 2, 3, 6, 5, 4, 7, 8, 9];
+		let temperatures: Vec<f64> = [(0, 1), (0, 6)]
+			.iter()
+			.map(|&(idx1, idx2)| {
+				let mut board = walk.clone();
+				board[idx1] = 0;
+				board[idx2] = 0;
+				let mut state = State::new(board, 3).unwrap();
+				state.set_board(walk.clone());
+				state.suggest_initial_temp(100, 1)
+			})
+			.collect();
+		assert_eq!(temperatures[1], 2.0 * temperatures[0]);
+		// with no free cells there is nothing to sample
+		let fixed =
+			State::new(vec![4, 5, 1, 3, 2, 6, 9, 8, 7], 3).unwrap();
+		assert_eq!(
+			fixed.suggest_initial_temp(100, 1),
+			START_TEMPERATURE
+		);
+		// the only swap of the two free cells raises the score, so no
+		// sample loses anything
+		let mut state =
+			State::new(vec![0, 0, 1, 3, 2, 6, 9, 8, 7], 3).unwrap();
+		state.set_board(vec![5, 4, 1, 3, 2, 6, 9, 8, 7]);
+		assert!(!state.is_solved());
+		assert_eq!(
+			state.suggest_initial_temp(100, 1),
+			START_TEMPERATURE
+		);
+	}
+	#[test]
+	fn anneal_from_should_suggest_a_temperature_for_zero() {
+		let puzzle = State::new(
+			vec![0, 0, 0, 0, 0, 3, 4, 0, 0, 0, 0, 0, 12, 0, 0, 0],
+			4,
+		)
+		.unwrap();
+		let mut state = puzzle.clone();
+		assert!((0..100).any(|_| state.anneal_from(10_000, 0.0)));
+		assert!(state.is_solved());
+	}
+	#[test]
+	fn weighted_score_should_match_score_with_unit_weights() {
+		let mut state = State::new_empty(4).unwrap();
+		for _ in 0..20 {
+			state.random_start();
+			assert_eq!(
+				state.weighted_score(&[]),
+				state.score() as f64
+			);
+			assert_eq!(
+				state.weighted_score(&[1.0; 15]),
+				state.score() as f64
+			);
+		}
+	}
+	#[test]
+	fn anneal_weighted_should_survive_non_finite_weights() {
+		let puzzle = State::new(
+			vec![0, 0, 0, 0, 0, 3, 4, 0, 0, 0, 0, 0, 12, 0, 0, 0],
+			4,
+		)
+		.unwrap();
+		let mut weights = [1.0; 15];
+		weights[7] = f64::NAN;
+		weights[9] = f64::INFINITY;
+		weights[10] = f64::NEG_INFINITY;
+		for _ in 0..20 {
+			let mut state = puzzle.clone();
+			state.random_start();
+			let solved = state.anneal_weighted(2_000, &weights);
+			assert_eq!(solved, state.score() == state.max_score());
+			assert!(state.positions_consistent());
+			assert_eq!(state.board[5], 3);
+			assert_eq!(state.board[12], 12);
+		}
+	}
+}
@@ -0,0 +1,63 @@
+use super::{KingsWalkError, State};
+use ndarray::Array2;
+
+impl State {
+	// The board as an n x n array, row by row
+	pub fn to_array2(&self) -> Array2<u8> {
+		Array2::from_shape_vec((self.n, self.n), self.board.clone())
+			.expect("the board length is always n*n")
+	}
+	// Make a new state from a square array, with 0 marking the free
+	// cells
+	pub fn from_array2(
+		arr: &Array2<u8>,
+	) -> Result<State, KingsWalkError> {
+		let (rows, cols) = arr.dim();
+		if rows != cols {
+			return Err(KingsWalkError::BoardLength);
+		}
+		// iter walks the array in row-major order whatever its memory
+		// layout is
+		State::new(arr.iter().copied().collect(), rows)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use ndarray::array;
+
+	#[test]
+	fn to_array2_should_be_row_major() {
+		let state =
+			State::new(vec![3, 4, 1, 8, 2, 5, 9, 7, 6], 3).unwrap();
+		assert_eq!(
+			state.to_array2(),
+			array![[3, 4, 1], [8, 2, 5], [9, 7, 6]]
+		);
+	}
+	#[test]
+	fn from_array2_should_round_trip_a_transpose() {
+		let state =
+			State::new(vec![3, 4, 1, 8, 2, 5, 9, 7, 6], 3).unwrap();
+		let transposed = state.to_array2().reversed_axes();
+		let back = State::from_array2(&transposed).unwrap();
+		// 3 8 9
+		// 4 2 7
+		// 1 5 6 is still a walk
+		assert_eq!(back.board, vec![3, 8, 9, 4, 2, 7, 1, 5, 6]);
+		assert_eq!(back.score(), back.max_score());
+		// a transpose is a quarter turn clockwise then a mirror left
+		// to right
+		assert_ne!(back.board, state.board);
+		assert_eq!(back.board, state.rotated().reflected().board);
+	}
+	#[test]
+	fn from_array2_should_reject_non_square_arrays() {
+		let arr = Array2::zeros((2, 3));
+		assert_eq!(
+			State::from_array2(&arr).unwrap_err(),
+			KingsWalkError::BoardLength
+		);
+	}
+}
@@ -0,0 +1,113 @@
+use super::{seed_rng, RandomRestart, State};
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+// The ways bakeoff can solve a puzzle, each with the same budget
+// auto_solve gives it
+#[derive(
+	Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash,
+)]
+pub enum Strategy {
+	// hillclimb with random restarts, giving up after 10_000
+	// restarts
+	Hillclimb,
+	// anneal, giving up after 100 runs of 20_000 swaps
+	Anneal,
+	// hillclimb_extend_run, giving up after 10_000 restarts
+	ExtendRun,
+	// solve_exact, only practical for small boards
+	Exact,
+	// solve_hamiltonian, only practical for small boards
+	Hamiltonian,
+	// auto_solve
+	Auto,
+}
+
+impl Strategy {
+	// Try to solve state in place and return whether it solved
+	fn solve(self, state: &mut State) -> bool {
+		match self {
+			Strategy::Hillclimb => {
+				state.hillclimb_with_policy(&RandomRestart, 10_000)
+			}
+			Strategy::Anneal => {
+				(0..100).any(|_| state.anneal(20_000))
+			}
+			Strategy::ExtendRun => state.hillclimb_extend_run(10_000),
+			Strategy::Exact => state.solve_exact(),
+			Strategy::Hamiltonian => state.solve_hamiltonian(),
+			Strategy::Auto => state.auto_solve(),
+		}
+	}
+}
+
+// Run every strategy on a copy of every puzzle and report how many
+// puzzles each solved and how long it took over all of them. Before
+// each run the random number generator is reseeded from seed and the
+// puzzle's index, so every strategy sees the same random numbers on
+// the same puzzle.
+pub fn bakeoff(
+	puzzles: &[State],
+	strategies: &[Strategy],
+	seed: u64,
+) -> BTreeMap<Strategy, (usize, Duration)> {
+	let mut results = BTreeMap::new();
+	for &strategy in strategies {
+		let (solved, elapsed) = results
+			.entry(strategy)
+			.or_insert((0, Duration::default()));
+		for (i, puzzle) in puzzles.iter().enumerate() {
+			let mut state = puzzle.clone();
+			seed_rng(seed.wrapping_add(i as u64));
+			let start = Instant::now();
+			if strategy.solve(&mut state) {
+				*solved += 1;
+			}
+			*elapsed += start.elapsed();
+		}
+	}
+	results
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const STRATEGIES: [Strategy; 6] = [
+		Strategy::Hillclimb,
+		Strategy::Anneal,
+		Strategy::ExtendRun,
+		Strategy::Exact,
+		Strategy::Hamiltonian,
+		Strategy::Auto,
+	];
+
+	#[test]
+	fn bakeoff_should_solve_trivial_puzzles_with_every_strategy() {
+		let puzzles = vec![
+			State::new(vec![0, 0, 1, 0, 2, 0, 9, 0, 0], 3).unwrap(),
+			State::new(vec![1, 0, 3, 0, 5, 0, 7, 0, 9], 3).unwrap(),
+			State::new_empty(3).unwrap(),
+		];
+		let results = bakeoff(&puzzles, &STRATEGIES, 1);
+		assert_eq!(results.len(), STRATEGIES.len());
+		for (solved, _) in results.values() {
+			assert_eq!(*solved, puzzles.len());
+		}
+	}
+	#[test]
+	fn bakeoff_should_count_unsolvable_puzzles_as_unsolved() {
+		// 1 and 2 are fixed in opposite corners
+		let puzzles =
+			vec![State::new(vec![1, 0, 0, 0, 0, 0, 0, 0, 2], 3)
+				.unwrap()];
+		let results = bakeoff(
+			&puzzles,
+			&[Strategy::Exact, Strategy::Hamiltonian],
+			1,
+		);
+		for (solved, _) in results.values() {
+			assert_eq!(*solved, 0);
+		}
+	}
+}
@@ -0,0 +1,86 @@
+use super::{RandomRestart, RestartPolicy, State};
+use std::cell::Cell;
+use std::io::{self, Write};
+use std::time::Instant;
+
+// RandomRestart, counting how many restarts it makes
+struct CountedRestart {
+	restarts: Cell<usize>,
+}
+
+impl RestartPolicy for CountedRestart {
+	fn restart(&self, state: &mut State) -> usize {
+		self.restarts.set(self.restarts.get() + 1);
+		RandomRestart.restart(state)
+	}
+}
+
+// Hillclimb each puzzle in turn with up to max_restarts random
+// restarts, solving it in place, and write a line of JSON to out as
+// each one finishes, like
+// {"index":0,"solved":true,"restarts":3,"ms":12}
+// Returns whether each puzzle solved.
+pub fn solve_batch(
+	puzzles: &mut [State],
+	max_restarts: usize,
+	out: &mut dyn Write,
+) -> io::Result<Vec<bool>> {
+	let mut solved = Vec::with_capacity(puzzles.len());
+	for (index, state) in puzzles.iter_mut().enumerate() {
+		let policy = CountedRestart {
+			restarts: Cell::new(0),
+		};
+		let start = Instant::now();
+		let done = state.hillclimb_with_policy(&policy, max_restarts);
+		writeln!(
+			out,
+			"{{\"index\":{},\"solved\":{},\"restarts\":{},\"ms\":\
+			 {}}}",
+			index,
+			done,
+			policy.restarts.get(),
+			start.elapsed().as_millis()
+		)?;
+		// let anyone tailing the output see the line straight away
+		out.flush()?;
+		solved.push(done);
+	}
+	Ok(solved)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn solve_batch_should_write_a_line_per_puzzle() {
+		let mut puzzles = vec![
+			State::new(vec![0, 0, 1, 0, 2, 0, 9, 0, 0], 3).unwrap(),
+			// 1 and 2 are fixed in opposite corners
+			State::new(vec![1, 0, 0, 0, 0, 0, 0, 0, 2], 3).unwrap(),
+			State::new_empty(4).unwrap(),
+		];
+		let mut out = Vec::new();
+		let solved =
+			solve_batch(&mut puzzles, 1_000, &mut out).unwrap();
+		assert_eq!(solved, vec![true, false, true]);
+		let out = String::from_utf8(out).unwrap();
+		let lines: Vec<&str> = out.lines().collect();
+		assert_eq!(lines.len(), 3);
+		for (index, line) in lines.iter().enumerate() {
+			let prefix = format!(
+				"{{\"index\":{},\"solved\":{},\"restarts\":",
+				index, solved[index]
+			);
+			assert!(line.starts_with(&prefix), "{}", line);
+			assert!(line.contains(",\"ms\":"), "{}", line);
+			assert!(line.ends_with('}'), "{}", line);
+		}
+		// the unsolvable puzzle used up every restart
+		assert!(
+			lines[1].contains("\"restarts\":1000,"),
+			"{}",
+			lines[1]
+		);
+	}
+}
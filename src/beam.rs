@@ -0,0 +1,128 @@
+use super::{ScoredState, State};
+use std::cmp::Reverse;
+use std::collections::HashSet;
+
+// How many swaps of each state in the beam become its children
+const BRANCHES: usize = 4;
+
+impl State {
+	// Beam search: keep the width best states found so far, starting
+	// from width random starts. Every iteration each state in the
+	// beam makes its BRANCHES best swaps that lead to a board not
+	// seen before, worse ones included so the beam can cross
+	// plateaus, and the width best of all those children become the
+	// next beam. Stops once a child is solved, the beam runs dry or
+	// after max_iters iterations, leaving the best board seen and
+	// returning whether it solved.
+	pub fn beam_search(
+		&mut self,
+		width: usize,
+		max_iters: usize,
+	) -> bool {
+		if self.is_solved() {
+			return true;
+		}
+		let mut seen = HashSet::new();
+		let mut beam: Vec<ScoredState> = (0..width.max(1))
+			.map(|_| {
+				let mut state = self.clone();
+				state.random_start();
+				seen.insert(state.board.clone());
+				ScoredState::new(state)
+			})
+			.collect();
+		beam.sort_unstable_by(|a, b| b.cmp(a));
+		let mut best = beam[0].clone();
+		for _ in 0..max_iters {
+			if best.state().is_solved() {
+				break;
+			}
+			let mut children = Vec::new();
+			for scored in &beam {
+				children
+					.extend(scored.state().beam_children(&mut seen));
+			}
+			if children.is_empty() {
+				break;
+			}
+			children.sort_unstable_by(|a, b| b.cmp(a));
+			children.truncate(width.max(1));
+			if children[0] > best {
+				best = children[0].clone();
+			}
+			beam = children;
+		}
+		*self = best.into_state();
+		self.is_solved()
+	}
+	// The states made by the BRANCHES best swaps of this one that
+	// lead to boards not in seen, which they are then added to
+	fn beam_children(
+		&self,
+		seen: &mut HashSet<Vec<u8>>,
+	) -> Vec<ScoredState> {
+		let mut state = self.clone();
+		let free = state.assignments.len();
+		let mut swaps = Vec::new();
+		for first in 0..free {
+			for second in first + 1..free {
+				let (idx1, idx2) = (
+					state.assignments[first],
+					state.assignments[second],
+				);
+				if !state.swap_allowed(idx1, idx2) {
+					continue;
+				}
+				state.swap(idx1, idx2);
+				swaps.push((state.score(), idx1, idx2));
+				state.swap(idx1, idx2);
+			}
+		}
+		// best first, and in scan order among equals
+		swaps.sort_by_key(|&(score, _, _)| Reverse(score));
+		let mut children = Vec::with_capacity(BRANCHES);
+		for (_, idx1, idx2) in swaps {
+			if children.len() == BRANCHES {
+				break;
+			}
+			state.swap(idx1, idx2);
+			if seen.insert(state.board.clone()) {
+				children.push(ScoredState::new(state.clone()));
+			}
+			state.swap(idx1, idx2);
+		}
+		children
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::super::{random_puzzle, seed_rng, RandomRestart};
+	use super::*;
+
+	#[test]
+	fn beam_search_should_solve_n_eq_5() {
+		seed_rng(169);
+		let puzzle = random_puzzle(5, 20).unwrap();
+		// plain hillclimbing is still stuck after 20 restarts
+		seed_rng(0);
+		assert!(!puzzle
+			.clone()
+			.hillclimb_with_policy(&RandomRestart, 20));
+		let mut state = puzzle.clone();
+		assert!(state.beam_search(8, 1_000));
+		assert_eq!(state.score(), state.max_score());
+		assert_eq!(
+			state.to_puzzle_string(),
+			puzzle.to_puzzle_string()
+		);
+	}
+	#[test]
+	fn beam_search_should_keep_the_best_board() {
+		// 1 and 2 are fixed in opposite corners
+		let mut state =
+			State::new(vec![1, 0, 0, 0, 0, 0, 0, 0, 2], 3).unwrap();
+		assert!(!state.beam_search(4, 100));
+		assert_eq!(state.score(), state.max_score() - 1);
+	}
+}
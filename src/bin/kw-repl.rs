@@ -0,0 +1,102 @@
+// Read commands from stdin and apply them to a single board:
+//
+// new <n>          start over with an empty n x n board
+// set <r> <c> <v>  put v in the free cell at row r, column c
+// show             print the board
+// score            print the score out of the max score
+// solve            solve the board
+// hint             suggest the best swap
+// undo             take back the last change
+use kings_walk::{KingsWalkError, State, MAX_N_U8};
+use std::convert::TryFrom;
+use std::io::{self, BufRead};
+
+fn main() {
+	let mut state: Option<State> = None;
+	// the boards before each change, for undo
+	let mut history: Vec<Option<State>> = Vec::new();
+	let stdin = io::stdin();
+	for line in stdin.lock().lines() {
+		let line = line.expect("failed to read stdin");
+		let words: Vec<&str> = line.split_whitespace().collect();
+		let (command, args) = match words.split_first() {
+			Some((command, args)) => (*command, args),
+			None => continue,
+		};
+		let numbers: Vec<usize> =
+			match args.iter().map(|arg| arg.parse()).collect() {
+				Ok(numbers) => numbers,
+				Err(_) => {
+					println!("arguments must be numbers");
+					continue;
+				}
+			};
+		match (command, numbers.as_slice(), state.as_mut()) {
+			("new", &[n], _) if n > MAX_N_U8 => {
+				println!("{}", KingsWalkError::BoardTooLarge);
+			}
+			("new", &[n], _) => match State::new(vec![0; n * n], n) {
+				Ok(new) => {
+					history.push(state.replace(new));
+					show(&state);
+				}
+				Err(e) => println!("{}", e),
+			},
+			("set", &[row, col, value], Some(current)) => {
+				let before = current.clone();
+				// anything too large for a u8 is out of range too
+				let value = u8::try_from(value).unwrap_or(0);
+				match current.try_place(row, col, value) {
+					Ok(()) => {
+						history.push(Some(before));
+						show(&state);
+					}
+					Err(e) => println!("{}", e),
+				}
+			}
+			("show", &[], _) => show(&state),
+			("score", &[], Some(current)) => {
+				println!(
+					"{} / {}",
+					current.score(),
+					current.max_score()
+				);
+			}
+			("solve", &[], Some(current)) => {
+				history.push(Some(current.clone()));
+				if !current.auto_solve() {
+					println!("no solution found");
+				}
+				show(&state);
+			}
+			("hint", &[], Some(current)) => match current.hint() {
+				Some(((r1, c1), (r2, c2))) => {
+					println!(
+						"swap ({}, {}) and ({}, {})",
+						r1, c1, r2, c2
+					);
+				}
+				None => println!("no swap raises the score"),
+			},
+			("undo", &[], _) => match history.pop() {
+				Some(previous) => {
+					state = previous;
+					show(&state);
+				}
+				None => println!("nothing to undo"),
+			},
+			("set", _, None)
+			| ("score", _, None)
+			| ("solve", _, None)
+			| ("hint", _, None) => println!("no board, use new <n>"),
+			_ => println!("unknown command: {}", line.trim()),
+		}
+	}
+}
+
+fn show(state: &Option<State>) {
+	match state {
+		Some(state) => print!("{}", state),
+		None => println!("no board, use new <n>"),
+	}
+}
@@ -0,0 +1,137 @@
+use super::{RandomRestart, RestartPolicy, State};
+
+// Settings for hillclimbing gathered up front, as in
+// SolverBuilder::new().max_restarts(100).plateau_limit(20).solve(&mut
+// state). By default it hillclimbs like hillclimb: random restarts,
+// never giving up, each climb running to its local maximum.
+pub struct SolverBuilder {
+	policy: Box<dyn RestartPolicy>,
+	max_restarts: usize,
+	plateau_limit: Option<usize>,
+}
+
+impl Default for SolverBuilder {
+	fn default() -> SolverBuilder {
+		SolverBuilder::new()
+	}
+}
+
+impl SolverBuilder {
+	pub fn new() -> SolverBuilder {
+		SolverBuilder {
+			policy: Box::new(RandomRestart),
+			max_restarts: usize::MAX,
+			plateau_limit: None,
+		}
+	}
+	// Where to start over once a climb is done
+	pub fn policy(
+		mut self,
+		policy: impl RestartPolicy + 'static,
+	) -> SolverBuilder {
+		self.policy = Box::new(policy);
+		self
+	}
+	// Give up after this many restarts
+	pub fn max_restarts(
+		mut self,
+		max_restarts: usize,
+	) -> SolverBuilder {
+		self.max_restarts = max_restarts;
+		self
+	}
+	// Abandon a climb and restart after this many steps, so one slow
+	// climb up a long gentle slope can't use up the whole budget. The
+	// policy's own step limit still applies when it is lower.
+	pub fn plateau_limit(mut self, steps: usize) -> SolverBuilder {
+		self.plateau_limit = Some(steps);
+		self
+	}
+	// Hillclimb state with these settings and return whether it
+	// solved. When it gives up the board is left at the highest
+	// scoring arrangement seen, as with hillclimb_with_policy.
+	pub fn solve(&self, state: &mut State) -> bool {
+		match self.plateau_limit {
+			Some(limit) => state.hillclimb_with_policy(
+				&PlateauLimit {
+					policy: &*self.policy,
+					limit,
+				},
+				self.max_restarts,
+			),
+			None => state.hillclimb_with_policy(
+				&*self.policy,
+				self.max_restarts,
+			),
+		}
+	}
+}
+
+// A policy that restarts like policy, with its step limit lowered to
+// limit
+struct PlateauLimit<'a> {
+	policy: &'a dyn RestartPolicy,
+	limit: usize,
+}
+
+impl RestartPolicy for PlateauLimit<'_> {
+	fn restart(&self, state: &mut State) -> usize {
+		self.policy.restart(state)
+	}
+	fn step_limit(&self, restart: usize) -> Option<usize> {
+		let limit = self
+			.policy
+			.step_limit(restart)
+			.map_or(self.limit, |steps| steps.min(self.limit));
+		Some(limit)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::super::{seed_rng, LubyRestart};
+	use super::*;
+
+	#[test]
+	fn plateau_limit_should_still_solve_an_easy_board() {
+		seed_rng(4);
+		for limit in 1..4 {
+			let mut state =
+				State::new(vec![0, 0, 1, 0, 2, 0, 9, 0, 0], 3)
+					.unwrap();
+			assert!(SolverBuilder::new()
+				.plateau_limit(limit)
+				.max_restarts(10_000)
+				.solve(&mut state));
+			assert!(state.is_solved());
+		}
+	}
+	#[test]
+	fn plateau_limit_should_lower_the_policy_step_limit() {
+		let luby = LubyRestart { unit: 2 };
+		let capped = PlateauLimit {
+			policy: &luby,
+			limit: 3,
+		};
+		// luby runs 2, 2, 4, 2, 2, 4, 8 steps
+		let limits: Vec<_> =
+			(1..=7).map(|r| capped.step_limit(r).unwrap()).collect();
+		assert_eq!(limits, vec![2, 2, 3, 2, 2, 3, 3]);
+		let capped = PlateauLimit {
+			policy: &RandomRestart,
+			limit: 5,
+		};
+		assert_eq!(capped.step_limit(1), Some(5));
+	}
+	#[test]
+	fn solver_builder_should_give_up_after_max_restarts() {
+		// 1 and 2 are fixed in opposite corners
+		let board = vec![1, 0, 0, 0, 0, 0, 0, 0, 2];
+		let mut state = State::new(board, 3).unwrap();
+		assert!(!SolverBuilder::new()
+			.policy(LubyRestart { unit: 1 })
+			.max_restarts(20)
+			.solve(&mut state));
+		assert_eq!(state.score(), state.upper_bound());
+	}
+}
@@ -0,0 +1,105 @@
+use super::{KingsWalkError, State, MAX_N_U8};
+
+impl State {
+	// Write the puzzle compactly: n as an LEB128 varint, then the n*n
+	// cells in board order with 0 for each free cell. Constraints and
+	// breaks are not stored.
+	pub fn to_bytes(&self) -> Vec<u8> {
+		let mut bytes = Vec::with_capacity(1 + self.board.len());
+		let mut n = self.n;
+		loop {
+			let low = (n & 0x7f) as u8;
+			n >>= 7;
+			if n == 0 {
+				bytes.push(low);
+				break;
+			}
+			bytes.push(low | 0x80);
+		}
+		let start = bytes.len();
+		bytes.extend_from_slice(&self.board);
+		for &idx in &self.assignments {
+			bytes[start + idx] = 0;
+		}
+		bytes
+	}
+	// Read a puzzle written by to_bytes
+	pub fn from_bytes(bytes: &[u8]) -> Result<State, KingsWalkError> {
+		let mut n = 0usize;
+		let mut read = 0;
+		loop {
+			let byte = *bytes
+				.get(read)
+				.ok_or(KingsWalkError::BoardLength)?;
+			n |= ((byte & 0x7f) as usize) << (7 * read);
+			read += 1;
+			// any n past MAX_N_U8 is rejected, so there is no need
+			// to read more than one byte beyond what it takes
+			if n > MAX_N_U8 || read > 1 && byte & 0x80 != 0 {
+				return Err(KingsWalkError::BoardTooLarge);
+			}
+			if byte & 0x80 == 0 {
+				break;
+			}
+		}
+		State::new(bytes[read..].to_vec(), n)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use proptest::prelude::{any, prop, proptest};
+
+	#[test]
+	fn bytes_should_round_trip() {
+		let state =
+			State::new(vec![0, 0, 1, 0, 2, 0, 9, 0, 0], 3).unwrap();
+		let bytes = state.to_bytes();
+		assert_eq!(bytes, vec![3, 0, 0, 1, 0, 2, 0, 9, 0, 0]);
+		let read = State::from_bytes(&bytes).unwrap();
+		assert_eq!(read.board, state.board);
+		assert_eq!(read.assignments, state.assignments);
+	}
+	#[test]
+	fn from_bytes_should_reject_bad_input() {
+		assert_eq!(
+			State::from_bytes(&[]).unwrap_err(),
+			KingsWalkError::BoardLength
+		);
+		// too short and too long
+		assert_eq!(
+			State::from_bytes(&[3, 0, 0, 1, 0, 2, 0, 9, 0])
+				.unwrap_err(),
+			KingsWalkError::BoardLength
+		);
+		assert_eq!(
+			State::from_bytes(&[3, 0, 0, 1, 0, 2, 0, 9, 0, 0, 0])
+				.unwrap_err(),
+			KingsWalkError::BoardLength
+		);
+		// n runs off the end, or past what a u8 board can hold
+		assert_eq!(
+			State::from_bytes(&[0x80]).unwrap_err(),
+			KingsWalkError::BoardLength
+		);
+		assert_eq!(
+			State::from_bytes(&[0xff; 16]).unwrap_err(),
+			KingsWalkError::BoardTooLarge
+		);
+		assert_eq!(
+			State::from_bytes(&[3, 0, 0, 1, 0, 2, 0, 1, 0, 0])
+				.unwrap_err(),
+			KingsWalkError::DuplicateValue(6)
+		);
+	}
+
+	proptest! {
+		#[test]
+		fn from_bytes_should_not_panic(
+			bytes in prop::collection::vec(any::<u8>(), 0..64)
+		) {
+			let _ = State::from_bytes(&bytes);
+		}
+	}
+}
@@ -0,0 +1,81 @@
+use super::{Difficulty, KingsWalkError, State};
+use serde::Deserialize;
+
+// A puzzle from a catalog along with what the catalog says about it
+#[derive(Debug, Clone)]
+pub struct PuzzleEntry {
+	pub name: Option<String>,
+	pub difficulty: Option<Difficulty>,
+	pub state: State,
+}
+
+// One puzzle as written in the JSON, with 0 for each free cell
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawEntry {
+	name: Option<String>,
+	difficulty: Option<Difficulty>,
+	n: usize,
+	board: Vec<u8>,
+}
+
+// Read a catalog of puzzles from a JSON array of objects like
+// {"name": "first", "difficulty": "Easy", "n": 3,
+//  "board": [0, 0, 1, 0, 2, 0, 9, 0, 0]}
+// where name and difficulty may be left out. Errors with the line of
+// the problem when the JSON doesn't read as that, and otherwise with
+// the first puzzle State::new rejects.
+pub fn load_catalog(
+	json: &str,
+) -> Result<Vec<PuzzleEntry>, KingsWalkError> {
+	let raw: Vec<RawEntry> = serde_json::from_str(json)
+		.map_err(|err| KingsWalkError::InvalidCatalog(err.line()))?;
+	raw.into_iter()
+		.map(|entry| {
+			Ok(PuzzleEntry {
+				name: entry.name,
+				difficulty: entry.difficulty,
+				state: State::new(entry.board, entry.n)?,
+			})
+		})
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn load_catalog_should_read_every_puzzle() {
+		let json = r#"[
+			{"name": "corner", "difficulty": "Easy", "n": 3,
+			 "board": [0, 0, 1, 0, 2, 0, 9, 0, 0]},
+			{"n": 4, "board": [9, 8, 7, 6, 0, 3, 4, 0,
+			                   0, 0, 0, 0, 12, 0, 0, 0]}
+		]"#;
+		let catalog = load_catalog(json).unwrap();
+		assert_eq!(catalog.len(), 2);
+		assert_eq!(catalog[0].name.as_deref(), Some("corner"));
+		assert_eq!(catalog[0].difficulty, Some(Difficulty::Easy));
+		assert_eq!(catalog[1].name, None);
+		assert_eq!(catalog[1].difficulty, None);
+		for entry in catalog {
+			let mut state = entry.state;
+			assert!(state.solve_exact());
+			assert!(state.is_solved());
+		}
+	}
+	#[test]
+	fn load_catalog_should_reject_bad_catalogs() {
+		assert_eq!(
+			load_catalog("[\n{\"n\": 3}\n]").unwrap_err(),
+			KingsWalkError::InvalidCatalog(2)
+		);
+		assert_eq!(
+			load_catalog(r#"[{"n": 2, "board": [1, 1, 0, 0]}]"#)
+				.unwrap_err(),
+			KingsWalkError::DuplicateValue(1)
+		);
+		assert_eq!(load_catalog("[]").unwrap().len(), 0);
+	}
+}
@@ -0,0 +1,90 @@
+use super::{KingsWalkError, State};
+
+// Check a submitted solution to puzzle. Errors when candidate isn't a
+// permutation of [1,n*n] or changes one of the puzzle's fixed cells,
+// and otherwise returns whether it solves the puzzle, constraints and
+// restrictions and all.
+pub fn check_solution(
+	puzzle: &State,
+	candidate: &[u8],
+) -> Result<bool, KingsWalkError> {
+	let cells = puzzle.board.len();
+	if candidate.len() != cells {
+		return Err(KingsWalkError::BoardLength);
+	}
+	let mut seen = vec![false; cells + 1];
+	for (idx, &value) in candidate.iter().enumerate() {
+		if value == 0 || value as usize > cells {
+			return Err(KingsWalkError::ValueOutOfRange(idx));
+		}
+		if seen[value as usize] {
+			return Err(KingsWalkError::DuplicateValue(idx));
+		}
+		seen[value as usize] = true;
+	}
+	for (idx, (&value, &clue)) in
+		candidate.iter().zip(&puzzle.board).enumerate()
+	{
+		let fixed = !puzzle.is_free(idx);
+		if fixed && value != clue {
+			return Err(KingsWalkError::ClueMismatch(idx));
+		}
+	}
+	let mut state = puzzle.clone();
+	state.set_board(candidate.to_vec());
+	Ok(state.is_solved())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn puzzle() -> State {
+		State::new(vec![0, 0, 1, 0, 2, 0, 9, 0, 0], 3).unwrap()
+	}
+
+	#[test]
+	fn check_solution_should_accept_a_solution() {
+		let candidate = [4, 5, 1, 3, 2, 6, 9, 8, 7];
+		assert_eq!(check_solution(&puzzle(), &candidate), Ok(true));
+		// a permutation that keeps the clues but isn't a walk
+		let candidate = [3, 4, 1, 5, 2, 6, 9, 7, 8];
+		assert_eq!(check_solution(&puzzle(), &candidate), Ok(false));
+	}
+	#[test]
+	fn check_solution_should_reject_a_restricted_value() {
+		let mut puzzle = puzzle();
+		puzzle.restrict(0, &[4]);
+		let candidate = [4, 5, 1, 3, 2, 6, 9, 8, 7];
+		assert_eq!(check_solution(&puzzle, &candidate), Ok(true));
+		// a walk, but with 6 where 4 is required
+		let candidate = [6, 5, 1, 7, 2, 4, 9, 8, 3];
+		assert_eq!(check_solution(&puzzle, &candidate), Ok(false));
+	}
+	#[test]
+	fn check_solution_should_reject_a_changed_clue() {
+		// a walk, but with 1 and 9 in each other's places
+		let candidate = [6, 7, 9, 5, 8, 4, 1, 2, 3];
+		assert_eq!(
+			check_solution(&puzzle(), &candidate),
+			Err(KingsWalkError::ClueMismatch(2))
+		);
+	}
+	#[test]
+	fn check_solution_should_reject_a_non_permutation() {
+		let candidate = [4, 3, 1, 5, 2, 6, 9, 8, 4];
+		assert_eq!(
+			check_solution(&puzzle(), &candidate),
+			Err(KingsWalkError::DuplicateValue(8))
+		);
+		let candidate = [4, 3, 1, 5, 2, 6, 9, 8, 10];
+		assert_eq!(
+			check_solution(&puzzle(), &candidate),
+			Err(KingsWalkError::ValueOutOfRange(8))
+		);
+		assert_eq!(
+			check_solution(&puzzle(), &[1, 2, 3]),
+			Err(KingsWalkError::BoardLength)
+		);
+	}
+}
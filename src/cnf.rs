@@ -0,0 +1,127 @@
+use super::State;
+
+impl State {
+	// The puzzle as clauses for a SAT solver, in the DIMACS style:
+	// each clause is a list of literals that must have one true,
+	// where the variable for "cell idx holds value v" is idx * n*n +
+	// v, and a negative literal is its negation. With N = n*n the
+	// clauses are
+	// - N saying each cell holds a value,
+	// - N*(N-1)/2 per cell saying it holds no two values,
+	// - N saying each value is in a cell,
+	// - N*(N-1)/2 per value saying it is in no two cells,
+	// - N per link the walk needs, saying wherever a value is the
+	//   value it links to is a king move away, which covers every
+	//   pair of consecutive values not split by a break and every
+	//   constraint,
+	// - one per fixed cell holding it to its value, and
+	// - one per restricted cell listing the values it allows.
+	pub fn to_cnf(&self) -> Vec<Vec<i32>> {
+		let cells = self.board.len();
+		let var = |idx: usize, v: usize| (idx * cells + v) as i32;
+		let mut clauses = Vec::new();
+		for idx in 0..cells {
+			clauses.push((1..=cells).map(|v| var(idx, v)).collect());
+			for v in 1..=cells {
+				for w in v + 1..=cells {
+					clauses.push(vec![-var(idx, v), -var(idx, w)]);
+				}
+			}
+		}
+		for v in 1..=cells {
+			clauses.push((0..cells).map(|idx| var(idx, v)).collect());
+			for idx in 0..cells {
+				for other in idx + 1..cells {
+					clauses.push(vec![-var(idx, v), -var(other, v)]);
+				}
+			}
+		}
+		let links = (1..cells)
+			.filter(|&v| self.unbroken(v, v + 1))
+			.map(|v| (v, v + 1))
+			.chain(
+				self.constraints
+					.iter()
+					.map(|&(a, b)| (a as usize, b as usize)),
+			);
+		for (v, w) in links {
+			for idx in 0..cells {
+				let mut clause = vec![-var(idx, v)];
+				clause.extend(
+					self.king_neighbors(idx)
+						.into_iter()
+						.map(|d| var(d, w)),
+				);
+				clauses.push(clause);
+			}
+		}
+		for idx in 0..cells {
+			if !self.is_free(idx) {
+				clauses
+					.push(vec![var(idx, self.board[idx] as usize)]);
+			}
+		}
+		for (&idx, allowed) in &self.restrictions {
+			clauses.push(
+				allowed
+					.iter()
+					.map(|&v| var(idx, v as usize))
+					.collect(),
+			);
+		}
+		clauses
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// whether board, read as an assignment of the variables, has a
+	// true literal in every clause
+	fn satisfies(clauses: &[Vec<i32>], board: &[u8]) -> bool {
+		let cells = board.len();
+		let holds = |literal: i32| {
+			let var = literal.unsigned_abs() as usize;
+			let (idx, v) = ((var - 1) / cells, (var - 1) % cells + 1);
+			(board[idx] as usize == v) == (literal > 0)
+		};
+		clauses.iter().all(|clause| {
+			clause.iter().any(|&literal| holds(literal))
+		})
+	}
+
+	#[test]
+	fn to_cnf_should_count_every_clause() {
+		let state =
+			State::new(vec![0, 0, 1, 0, 2, 0, 9, 0, 0], 3).unwrap();
+		let clauses = state.to_cnf();
+		// cells and values, 8 links and 3 clues
+		assert_eq!(clauses.len(), 2 * (9 + 9 * 36) + 8 * 9 + 3);
+		let mut state =
+			State::with_segments(vec![0; 9], 3, &[4]).unwrap();
+		state.add_constraint(1, 9);
+		state.restrict(0, &[1, 2]);
+		assert_eq!(
+			state.to_cnf().len(),
+			2 * (9 + 9 * 36) + 8 * 9 + 1
+		);
+	}
+	#[test]
+	fn to_cnf_should_accept_only_solutions() {
+		let state =
+			State::new(vec![0, 0, 1, 0, 2, 0, 9, 0, 0], 3).unwrap();
+		let clauses = state.to_cnf();
+		assert!(satisfies(&clauses, &[4, 5, 1, 3, 2, 6, 9, 8, 7]));
+		// a walk, but not through the clues
+		assert!(!satisfies(&clauses, &[1, 2, 3, 6, 5, 4, 7, 8, 9]));
+		// the clues, but not a walk
+		assert!(!satisfies(&clauses, &[3, 4, 1, 5, 2, 6, 9, 8, 7]));
+		// every variable lies in range
+		let top = 81;
+		assert!(clauses
+			.iter()
+			.flatten()
+			.all(|&l| l != 0 && l.unsigned_abs() <= top));
+	}
+}
@@ -0,0 +1,101 @@
+use super::State;
+use std::fmt;
+
+// What to print after the board
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ScoreLine {
+	// score: 8
+	#[default]
+	Score,
+	// score: 8/8, the score out of the max score
+	ScoreOfMax,
+	// no score line, only the board
+	Hidden,
+}
+
+// How to print a state. The default prints it the same as Display.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DisplayConfig {
+	pub score_line: ScoreLine,
+}
+
+// A state printed the way config says, from State::display
+pub struct Displayed<'a> {
+	state: &'a State,
+	config: DisplayConfig,
+}
+
+impl State {
+	// Print the board the way config says, as in
+	// println!("{}", state.display(config))
+	pub fn display(&self, config: DisplayConfig) -> Displayed<'_> {
+		Displayed {
+			state: self,
+			config,
+		}
+	}
+}
+
+impl fmt::Display for Displayed<'_> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let state = self.state;
+		for line in state.board.as_slice().chunks(state.n) {
+			writeln!(f, "{:?}", line)?;
+		}
+		match self.config.score_line {
+			ScoreLine::Score => {
+				writeln!(f, "score: {}", state.score())
+			}
+			ScoreLine::ScoreOfMax => writeln!(
+				f,
+				"score: {}/{}",
+				state.score(),
+				state.max_score()
+			),
+			ScoreLine::Hidden => Ok(()),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn solved() -> State {
+		State::new(vec![4, 5, 1, 3, 2, 6, 9, 8, 7], 3).unwrap()
+	}
+
+	#[test]
+	fn display_should_default_to_the_score() {
+		let state = solved();
+		let shown =
+			state.display(DisplayConfig::default()).to_string();
+		assert_eq!(shown, state.to_string());
+		assert_eq!(
+			shown,
+			"[4, 5, 1]\n[3, 2, 6]\n[9, 8, 7]\nscore: 8\n"
+		);
+	}
+	#[test]
+	fn display_should_show_the_score_out_of_max() {
+		let mut state = solved();
+		state.board.swap(0, 8);
+		let config = DisplayConfig {
+			score_line: ScoreLine::ScoreOfMax,
+		};
+		assert_eq!(
+			state.display(config).to_string(),
+			"[7, 5, 1]\n[3, 2, 6]\n[9, 8, 4]\nscore: 4/8\n"
+		);
+	}
+	#[test]
+	fn display_should_hide_the_score() {
+		let config = DisplayConfig {
+			score_line: ScoreLine::Hidden,
+		};
+		assert_eq!(
+			solved().display(config).to_string(),
+			"[4, 5, 1]\n[3, 2, 6]\n[9, 8, 7]\n"
+		);
+	}
+}
@@ -0,0 +1,556 @@
+use super::State;
+
+// The shape of the search tree explored by extend_walk: how many
+// nodes had children, and how many children there were in all
+#[derive(Debug, Default)]
+struct WalkStats {
+	internal: usize,
+	children: usize,
+}
+
+impl State {
+	// Search every walk through the board with backtracking. Places
+	// 1, 2, ... one king move apart, only ever putting a free value
+	// on a free cell, and accepts the first complete walk that meets
+	// the constraints. Returns whether a solution exists, in which
+	// case the board holds it. Only practical for small boards.
+	pub fn solve_exact(&mut self) -> bool {
+		let mut solution = None;
+		self.search_walks(&mut |path| {
+			solution = Some(path.to_vec());
+			true
+		});
+		match solution {
+			Some(path) => {
+				self.apply_walk(&path);
+				true
+			}
+			None => false,
+		}
+	}
+	// Every solution of the puzzle, found the same way as solve_exact
+	pub fn solutions(&self) -> Vec<State> {
+		let mut found = Vec::new();
+		self.search_walks(&mut |path| {
+			let mut solution = self.clone();
+			solution.apply_walk(path);
+			found.push(solution);
+			false
+		});
+		found
+	}
+	// The boards of at most max solutions, stopping the search as
+	// soon as that many are found
+	pub fn solutions_capped(&self, max: usize) -> Vec<Vec<u8>> {
+		let mut found = Vec::new();
+		if max == 0 {
+			return found;
+		}
+		self.search_walks(&mut |path| {
+			let mut solution = self.clone();
+			solution.apply_walk(path);
+			found.push(solution.into_board());
+			found.len() == max
+		});
+		found
+	}
+	// The free cells which hold the same value in every solution, so
+	// are already determined by the clues. Empty when there is no
+	// solution at all.
+	pub fn forced_cells(&self) -> Vec<usize> {
+		let solutions = self.solutions();
+		let first = match solutions.first() {
+			Some(first) => first,
+			None => return Vec::new(),
+		};
+		self.assignments
+			.iter()
+			.copied()
+			.filter(|&idx| {
+				solutions
+					.iter()
+					.all(|s| s.board[idx] == first.board[idx])
+			})
+			.collect()
+	}
+	// The fewest swaps of two free cells that turn the board into a
+	// solution, over every solution, or None when there is none.
+	// Only practical for small boards.
+	pub fn distance_to_solution(&self) -> Option<usize> {
+		let cells = self.board.len();
+		let mut nearest: Option<usize> = None;
+		let mut seen = vec![false; cells];
+		self.search_walks(&mut |path| {
+			// moving each value to its cell in path is a permutation
+			// of the cells, and sorting each of its cycles takes
+			// one swap fewer than its length
+			seen.iter_mut().for_each(|s| *s = false);
+			let mut cycles = 0;
+			for start in 0..cells {
+				if seen[start] {
+					continue;
+				}
+				cycles += 1;
+				let mut idx = start;
+				while !seen[idx] {
+					seen[idx] = true;
+					idx = path[self.board[idx] as usize - 1];
+				}
+			}
+			let distance = cells - cycles;
+			nearest =
+				Some(nearest.map_or(distance, |d| d.min(distance)));
+			distance == 0
+		});
+		nearest
+	}
+	// Solve by backtracking over the free cells rather than the
+	// values: fill the most constrained cell first with each of its
+	// legal values in turn. Returns whether a solution exists, in
+	// which case the board holds it, and otherwise leaves the board
+	// as it was. Only practical for small boards.
+	pub fn solve_mrv(&mut self) -> bool {
+		let mut remaining: Vec<u8> = self
+			.assignments
+			.iter()
+			.map(|&idx| self.board[idx])
+			.collect();
+		remaining.sort_unstable();
+		let before = self.board.clone();
+		if self.place_remaining(&mut remaining) {
+			return true;
+		}
+		self.set_board(before);
+		false
+	}
+	// The free cell still holding one of the remaining (sorted,
+	// unplaced) values with the fewest legal values, the first such
+	// cell on ties, or None when every free cell is placed. This is
+	// the minimum remaining values heuristic.
+	fn most_constrained_cell(
+		&self,
+		remaining: &[u8],
+	) -> Option<usize> {
+		self.assignments
+			.iter()
+			.copied()
+			.filter(|&idx| {
+				remaining.binary_search(&self.board[idx]).is_ok()
+			})
+			.min_by_key(|&idx| {
+				self.legal_values(idx, remaining).len()
+			})
+	}
+	// The remaining values that could go in the cell at idx: those it
+	// allows whose placed neighbors in the walk are a king move away
+	fn legal_values(&self, idx: usize, remaining: &[u8]) -> Vec<u8> {
+		let last = self.board.len();
+		let linked = |w: usize| {
+			remaining.binary_search(&(w as u8)).is_ok()
+				|| self.king_distance(idx, self.pos[w]) == 1
+		};
+		remaining
+			.iter()
+			.copied()
+			.filter(|&v| {
+				let v = v as usize;
+				self.allows(idx, v as u8)
+					&& (v == 1
+						|| !self.unbroken(v - 1, v)
+						|| linked(v - 1))
+					&& (v == last
+						|| !self.unbroken(v, v + 1)
+						|| linked(v + 1))
+			})
+			.collect()
+	}
+	// Place the remaining values one cell at a time, returning
+	// whether that reached a solution
+	fn place_remaining(&mut self, remaining: &mut Vec<u8>) -> bool {
+		let idx = match self.most_constrained_cell(remaining) {
+			Some(idx) => idx,
+			None => return self.is_solved(),
+		};
+		for v in self.legal_values(idx, remaining) {
+			// v is in an unplaced cell, so this only ever moves
+			// unplaced values
+			self.swap(idx, self.pos[v as usize]);
+			let at = remaining.binary_search(&v).unwrap();
+			remaining.remove(at);
+			if self.place_remaining(remaining) {
+				return true;
+			}
+			remaining.insert(at, v);
+		}
+		false
+	}
+	// The average number of children of the nodes with any in the
+	// exact solver's full search tree, or 0 when no node has any. The
+	// fewer choices the clues leave, the lower it is.
+	// Only practical for small boards.
+	pub fn branching_factor(&self) -> f64 {
+		let mut stats = WalkStats::default();
+		self.search_walks_counting(&mut |_| false, &mut stats);
+		if stats.internal == 0 {
+			return 0.0;
+		}
+		stats.children as f64 / stats.internal as f64
+	}
+	// Call visit with every complete walk that meets the constraints,
+	// where path[i] is the position of the value i+1. Stops early
+	// once visit returns true.
+	fn search_walks(&self, visit: &mut dyn FnMut(&[usize]) -> bool) {
+		self.search_walks_counting(visit, &mut WalkStats::default());
+	}
+	// search_walks, adding the nodes it explores to stats
+	fn search_walks_counting(
+		&self,
+		visit: &mut dyn FnMut(&[usize]) -> bool,
+		stats: &mut WalkStats,
+	) {
+		let (fixed, mut used) = self.walk_clues();
+		let mut path = Vec::with_capacity(self.board.len());
+		self.extend_walk(&fixed, &mut used, &mut path, visit, stats);
+	}
+	// Where each fixed value sits, indexed by value, and which cells
+	// are taken before the walk starts
+	pub(crate) fn walk_clues(
+		&self,
+	) -> (Vec<Option<usize>>, Vec<bool>) {
+		let cells = self.board.len();
+		let mut used = vec![true; cells];
+		for &idx in &self.assignments {
+			used[idx] = false;
+		}
+		let mut fixed = vec![None; cells + 1];
+		for (idx, &value) in self.board.iter().enumerate() {
+			if used[idx] {
+				fixed[value as usize] = Some(idx);
+			}
+		}
+		(fixed, used)
+	}
+	// Whether a complete walk, where path[i] is the position of the
+	// value i+1, meets every constraint
+	pub(crate) fn walk_meets_constraints(
+		&self,
+		path: &[usize],
+	) -> bool {
+		self.constraints.iter().all(|&(a, b)| {
+			let idx1 = path[a as usize - 1];
+			let idx2 = path[b as usize - 1];
+			self.king_distance(idx1, idx2) == 1
+		})
+	}
+	// Write a walk found by search_walks onto the board
+	pub(crate) fn apply_walk(&mut self, path: &[usize]) {
+		for (value, &idx) in path.iter().enumerate() {
+			self.board[idx] = value as u8 + 1;
+			self.pos[value + 1] = idx;
+		}
+	}
+	// Try to continue the walk in path with the next value. Returns
+	// true once visit asks to stop.
+	fn extend_walk(
+		&self,
+		fixed: &[Option<usize>],
+		used: &mut [bool],
+		path: &mut Vec<usize>,
+		visit: &mut dyn FnMut(&[usize]) -> bool,
+		stats: &mut WalkStats,
+	) -> bool {
+		let value = path.len() + 1;
+		if value == fixed.len() {
+			// a full walk, which must also meet every constraint
+			return self.walk_meets_constraints(path) && visit(path);
+		}
+		let candidates = self.walk_candidates(fixed, path);
+		if let Some(idx) = fixed[value] {
+			if !candidates.contains(&idx) {
+				return false;
+			}
+			stats.internal += 1;
+			stats.children += 1;
+			path.push(idx);
+			if self.extend_walk(fixed, used, path, visit, stats) {
+				return true;
+			}
+			path.pop();
+			return false;
+		}
+		// The next fixed value further along the walk must still be
+		// reachable in the moves that are left, unless the walk may
+		// jump on the way
+		let next_fixed = (value + 1..fixed.len())
+			.find_map(|v| fixed[v].map(|idx| (v, idx)))
+			.filter(|&(v, _)| self.unbroken(value, v));
+		let children = stats.children;
+		for idx in candidates {
+			if used[idx] || !self.allows(idx, value as u8) {
+				continue;
+			}
+			if let Some((v, target)) = next_fixed {
+				if self.king_distance(idx, target) > v - value {
+					continue;
+				}
+			}
+			if stats.children == children {
+				stats.internal += 1;
+			}
+			stats.children += 1;
+			used[idx] = true;
+			path.push(idx);
+			if self.extend_walk(fixed, used, path, visit, stats) {
+				return true;
+			}
+			path.pop();
+			used[idx] = false;
+		}
+		false
+	}
+	// The cells the next value of the walk in path may go in, before
+	// checking which are used. 1 may go anywhere, as may a value
+	// after a break, every other value next to its predecessor. When
+	// the value after it is fixed and linked to it, only the cells
+	// in that value's neighbor mask are kept.
+	fn walk_candidates(
+		&self,
+		fixed: &[Option<usize>],
+		path: &[usize],
+	) -> Vec<usize> {
+		let value = path.len() + 1;
+		let candidates = match path.last() {
+			Some(&prev) if self.unbroken(value - 1, value) => {
+				self.king_neighbors(prev)
+			}
+			_ => (0..self.board.len()).collect(),
+		};
+		match fixed.get(value + 1) {
+			Some(Some(_)) if self.unbroken(value, value + 1) => {
+				let mask = self.value_neighbor_mask(value as u8 + 1);
+				candidates
+					.into_iter()
+					.filter(|&idx| {
+						mask[idx / 64] >> (idx % 64) & 1 == 1
+					})
+					.collect()
+			}
+			_ => candidates,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn walk_candidates_should_keep_the_cells_beside_a_fixed_next_value(
+	) {
+		// 2 is fixed in the top left corner, so 1 can only go beside
+		// it rather than anywhere
+		let state =
+			State::new(vec![2, 0, 0, 0, 0, 0, 0, 0, 0], 3).unwrap();
+		let (fixed, _) = state.walk_clues();
+		assert_eq!(state.walk_candidates(&fixed, &[]), vec![1, 3, 4]);
+		// 2 beside the predecessor's cell as well as 4's
+		let state =
+			State::new(vec![0, 0, 0, 0, 0, 0, 0, 0, 4], 3).unwrap();
+		let (fixed, _) = state.walk_clues();
+		assert_eq!(
+			state.walk_candidates(&fixed, &[0, 1]),
+			vec![4, 5]
+		);
+		// with a break after 3 nothing is pruned
+		let state = State::with_segments(
+			vec![0, 0, 0, 0, 0, 0, 0, 0, 4],
+			3,
+			&[3],
+		)
+		.unwrap();
+		let (fixed, _) = state.walk_clues();
+		assert_eq!(
+			state.walk_candidates(&fixed, &[0, 1]),
+			vec![0, 2, 3, 4, 5]
+		);
+	}
+
+	#[test]
+	fn solve_exact_should_solve_n_eq_3() {
+		let mut state =
+			State::new(vec![0, 0, 1, 0, 2, 0, 9, 0, 0], 3).unwrap();
+		assert!(state.solve_exact());
+		assert_eq!(state.score(), state.max_score());
+		// the fixed cells are untouched
+		assert_eq!(state.board[2], 1);
+		assert_eq!(state.board[4], 2);
+		assert_eq!(state.board[6], 9);
+	}
+	#[test]
+	fn solve_exact_should_solve_n_eq_4() {
+		let mut state = State::new(
+			vec![9, 8, 7, 6, 0, 3, 4, 0, 0, 0, 0, 0, 12, 0, 0, 0],
+			4,
+		)
+		.unwrap();
+		assert!(state.solve_exact());
+		assert_eq!(state.score(), state.max_score());
+	}
+	#[test]
+	fn solve_exact_should_report_unsolvable() {
+		// 1 and 2 are fixed in opposite corners
+		let board = vec![1, 0, 0, 0, 0, 0, 0, 0, 2];
+		let mut state = State::new(board, 3).unwrap();
+		let before = state.board.clone();
+		assert!(!state.solve_exact());
+		assert_eq!(before, state.board);
+	}
+	#[test]
+	fn solutions_should_find_every_walk() {
+		// 1 . 3
+		// . . .
+		// 7 . 9 has three walks
+		let state =
+			State::new(vec![1, 0, 3, 0, 0, 0, 7, 0, 9], 3).unwrap();
+		let solutions = state.solutions();
+		assert_eq!(solutions.len(), 3);
+		for solution in solutions {
+			assert_eq!(solution.score(), solution.max_score());
+		}
+	}
+	#[test]
+	fn distance_to_solution_should_count_swaps() {
+		// has only the one solution
+		let mut state =
+			State::new(vec![1, 0, 3, 0, 5, 0, 7, 0, 9], 3).unwrap();
+		assert!(state.solve_exact());
+		assert_eq!(state.distance_to_solution(), Some(0));
+		state.swap(1, 7);
+		assert_eq!(state.distance_to_solution(), Some(1));
+		// a 3-cycle of free cells takes two swaps
+		state.swap(1, 7);
+		state.swap(1, 3);
+		state.swap(3, 5);
+		assert_eq!(state.distance_to_solution(), Some(2));
+		// 1 and 2 are fixed in opposite corners
+		let board = vec![1, 0, 0, 0, 0, 0, 0, 0, 2];
+		let state = State::new(board, 3).unwrap();
+		assert_eq!(state.distance_to_solution(), None);
+	}
+	#[test]
+	fn most_constrained_cell_should_pick_a_forced_cell() {
+		// 1 . 3
+		// 6 . 4
+		// 7 8 . where the corner can only be 9
+		let state =
+			State::new(vec![1, 0, 3, 6, 0, 4, 7, 8, 0], 3).unwrap();
+		let remaining = [2, 5, 9];
+		assert_eq!(state.legal_values(1, &remaining), vec![2, 5]);
+		assert_eq!(state.legal_values(4, &remaining), vec![2, 5, 9]);
+		assert_eq!(state.legal_values(8, &remaining), vec![9]);
+		assert_eq!(state.most_constrained_cell(&remaining), Some(8));
+		assert_eq!(state.most_constrained_cell(&[]), None);
+	}
+	#[test]
+	fn solve_mrv_should_solve() {
+		let mut state =
+			State::new(vec![0, 0, 1, 0, 2, 0, 9, 0, 0], 3).unwrap();
+		assert!(state.solve_mrv());
+		assert!(state.is_solved());
+		let mut state = State::new(
+			vec![9, 8, 7, 6, 0, 3, 4, 0, 0, 0, 0, 0, 12, 0, 0, 0],
+			4,
+		)
+		.unwrap();
+		assert!(state.solve_mrv());
+		assert!(state.is_solved());
+		let mut state = State::new_empty(4).unwrap();
+		state.add_constraint(1, 16);
+		assert!(state.solve_mrv());
+		assert!(state.is_solved());
+	}
+	#[test]
+	fn solve_mrv_should_report_unsolvable() {
+		// 1 and 2 are fixed in opposite corners
+		let board = vec![1, 0, 0, 0, 0, 0, 0, 0, 2];
+		let mut state = State::new(board, 3).unwrap();
+		let before = state.board.clone();
+		assert!(!state.solve_mrv());
+		assert_eq!(before, state.board);
+	}
+	#[test]
+	fn solutions_capped_should_stop_at_max() {
+		// the empty 3x3 board has 784 solutions
+		let state = State::new(vec![0; 9], 3).unwrap();
+		for max in 0..4 {
+			assert_eq!(state.solutions_capped(max).len(), max);
+		}
+		let all: Vec<Vec<u8>> = state
+			.solutions()
+			.into_iter()
+			.map(State::into_board)
+			.collect();
+		assert_eq!(state.solutions_capped(3), all[..3].to_vec());
+		// and a puzzle with fewer solutions gives all of them
+		let state =
+			State::new(vec![1, 0, 3, 0, 0, 0, 7, 0, 9], 3).unwrap();
+		assert_eq!(state.solutions_capped(10).len(), 3);
+	}
+	#[test]
+	fn solve_exact_should_respect_restrictions() {
+		// 1 . 3
+		// . . .
+		// 7 . 9 is solved with 2 above the center unless that cell
+		// may only hold 5
+		let puzzle =
+			State::new(vec![1, 0, 3, 0, 0, 0, 7, 0, 9], 3).unwrap();
+		let mut state = puzzle.clone();
+		assert!(state.solve_exact());
+		assert_eq!(state.board, vec![1, 2, 3, 6, 5, 4, 7, 8, 9]);
+		let mut state = puzzle.clone();
+		state.restrict(1, &[5]);
+		assert!(state.solve_exact());
+		assert_eq!(state.board, vec![1, 5, 3, 6, 2, 4, 7, 8, 9]);
+		assert_eq!(state.score(), state.max_score());
+		let mut state = puzzle;
+		state.restrict(1, &[4]);
+		assert!(!state.solve_exact());
+	}
+	#[test]
+	fn branching_factor_should_drop_with_more_clues() {
+		let sparse =
+			State::new(vec![0, 0, 1, 0, 0, 0, 0, 0, 0], 3).unwrap();
+		let dense =
+			State::new(vec![1, 0, 3, 0, 5, 0, 7, 0, 9], 3).unwrap();
+		assert!(dense.branching_factor() < sparse.branching_factor());
+		// a single walk has nothing to choose
+		let full =
+			State::new(vec![1, 2, 3, 6, 5, 4, 7, 8, 9], 3).unwrap();
+		assert_eq!(full.branching_factor(), 1.0);
+	}
+	#[test]
+	fn forced_cells_should_find_every_cell_of_a_unique_puzzle() {
+		// 1 2 3
+		// 6 5 4
+		// 7 8 9 is the only walk
+		let state =
+			State::new(vec![1, 0, 3, 0, 5, 0, 7, 0, 9], 3).unwrap();
+		assert_eq!(state.forced_cells(), vec![1, 3, 5, 7]);
+	}
+	#[test]
+	fn forced_cells_should_skip_ambiguous_cells() {
+		// 4 and 6 always sit at the ends of the middle row, but 2, 5
+		// and 8 can share the center and the middle of the top and
+		// bottom rows in several ways
+		let state =
+			State::new(vec![1, 0, 3, 0, 0, 0, 7, 0, 9], 3).unwrap();
+		assert_eq!(state.forced_cells(), vec![3, 5]);
+	}
+	#[test]
+	fn solve_exact_should_meet_constraints() {
+		let mut state = State::new(vec![0; 9], 3).unwrap();
+		state.add_constraint(1, 9);
+		assert!(state.solve_exact());
+		assert_eq!(state.score(), state.max_score());
+	}
+}
@@ -0,0 +1,188 @@
+use super::State;
+
+impl State {
+	// step, but first trying only the swaps that grow the longest run
+	// by a value: those moving the value just past either end of the
+	// run into a free cell beside that end. The best of them is made
+	// if it raises the score, and otherwise step picks from every
+	// swap. Returns the new score.
+	#[must_use]
+	pub fn step_extend_run(&mut self, start_score: usize) -> usize {
+		let (first, last) = self.longest_run();
+		let mut ends = Vec::new();
+		if last < self.board.len() && self.unbroken(last, last + 1) {
+			ends.push((self.pos[last], last + 1));
+		}
+		if first > 1 && self.unbroken(first - 1, first) {
+			ends.push((self.pos[first], first - 1));
+		}
+		let mut best = None;
+		let mut high_score = start_score;
+		for (end, value) in ends {
+			let from = self.pos[value];
+			if !self.is_free(from) {
+				continue;
+			}
+			for idx in self.king_neighbors(end) {
+				if idx == from
+					|| !self.is_free(idx)
+					|| !self.swap_allowed(idx, from)
+				{
+					continue;
+				}
+				self.swap(idx, from);
+				let score = self.score();
+				self.swap(idx, from);
+				if score > high_score {
+					high_score = score;
+					best = Some((idx, from));
+				}
+			}
+		}
+		match best {
+			Some((idx1, idx2)) => {
+				self.swap(idx1, idx2);
+				high_score
+			}
+			None => self.step(start_score),
+		}
+	}
+	// Hillclimb with random restarts like hillclimb, climbing with
+	// step_extend_run. Gives up after max_restarts and returns
+	// whether it solved, leaving the board at the highest scoring
+	// arrangement seen when it gives up.
+	pub fn hillclimb_extend_run(
+		&mut self,
+		max_restarts: usize,
+	) -> bool {
+		// with nothing free no restart can change the board
+		if self.assignments.is_empty() {
+			return self.is_solved();
+		}
+		self.hillclimb_extend_run_trace(max_restarts).0
+	}
+	// hillclimb_extend_run, recording (iteration, score) the way
+	// hillclimb_trace does, so the steps the two take can be
+	// compared
+	pub fn hillclimb_extend_run_trace(
+		&mut self,
+		max_restarts: usize,
+	) -> (bool, Vec<(usize, usize)>) {
+		let mut score = self.score();
+		let mut best = (score, self.board.clone());
+		let mut iteration = 0;
+		let mut trace = vec![(iteration, score)];
+		for restart in 0..=max_restarts {
+			if restart > 0 {
+				score = self.random_start();
+				iteration += 1;
+				trace.push((iteration, score));
+			}
+			loop {
+				let next = self.step_extend_run(score);
+				if next <= score {
+					break;
+				}
+				score = next;
+				iteration += 1;
+				trace.push((iteration, score));
+			}
+			if score == self.max_score() && self.is_solved() {
+				return (true, trace);
+			}
+			if score > best.0 {
+				best = (score, self.board.clone());
+			}
+		}
+		self.set_board(best.1);
+		trace.push((iteration + 1, self.score()));
+		(false, trace)
+	}
+	// The first and last values of the longest run of values each
+	// linked to the next, the lowest such run on ties
+	fn longest_run(&self) -> (usize, usize) {
+		let mut longest = (1, 1);
+		let mut first = 1;
+		for v in 1..self.board.len() {
+			let linked = self.unbroken(v, v + 1)
+				&& self.king_distance(self.pos[v], self.pos[v + 1])
+					== 1;
+			if !linked {
+				first = v + 1;
+			} else if v + 1 - first > longest.1 - longest.0 {
+				longest = (first, v + 1);
+			}
+		}
+		longest
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::seed_rng;
+
+	#[test]
+	fn longest_run_should_find_the_longest_linked_values() {
+		// 1 to 5 are linked, then 6 to 9
+		let state =
+			State::new(vec![1, 2, 3, 9, 8, 4, 6, 7, 5], 3).unwrap();
+		assert_eq!(state.longest_run(), (1, 5));
+		// 2 to 4 and 5 to 7 tie
+		let state =
+			State::new(vec![1, 9, 2, 8, 3, 7, 4, 6, 5], 3).unwrap();
+		assert_eq!(state.longest_run(), (2, 4));
+	}
+	#[test]
+	fn step_extend_run_should_grow_the_run() {
+		// the run 1 2 3 ends at the top right, out of reach of 4
+		let mut state =
+			State::new(vec![1, 2, 3, 0, 0, 0, 0, 0, 0], 3).unwrap();
+		state.set_board(vec![1, 2, 3, 4, 6, 5, 8, 9, 7]);
+		assert_eq!(state.longest_run(), (1, 3));
+		let score = state.score();
+		assert_eq!(state.step_extend_run(score), score + 1);
+		assert_eq!(state.board, vec![1, 2, 3, 5, 6, 4, 8, 9, 7]);
+		assert_eq!(state.longest_run(), (1, 4));
+	}
+	#[test]
+	fn hillclimb_extend_run_should_solve() {
+		seed_rng(2);
+		for board in [
+			vec![0, 0, 1, 0, 2, 0, 9, 0, 0],
+			vec![0, 0, 0, 0, 0, 3, 4, 0, 0, 0, 0, 0, 12, 0, 0, 0],
+		] {
+			let n = if board.len() == 9 { 3 } else { 4 };
+			let mut state = State::new(board, n).unwrap();
+			assert!(state.hillclimb_extend_run(10_000));
+			assert!(state.is_solved());
+		}
+		// 1 and 2 are fixed in opposite corners
+		let mut state =
+			State::new(vec![1, 0, 0, 0, 0, 0, 0, 0, 2], 3).unwrap();
+		assert!(!state.hillclimb_extend_run(20));
+		assert!(state.board_is_permutation());
+	}
+	#[test]
+	fn hillclimb_extend_run_trace_should_count_every_step() {
+		let puzzle = State::new(
+			vec![0, 0, 0, 0, 0, 3, 4, 0, 0, 0, 0, 0, 12, 0, 0, 0],
+			4,
+		)
+		.unwrap();
+		seed_rng(4);
+		let mut state = puzzle.clone();
+		let (solved, trace) =
+			state.hillclimb_extend_run_trace(10_000);
+		assert!(solved);
+		assert!(trace
+			.windows(2)
+			.all(|pair| pair[0].0 + 1 == pair[1].0));
+		assert_eq!(trace.last().unwrap().1, state.max_score());
+		// the same seed solves the same way untraced
+		seed_rng(4);
+		let mut untraced = puzzle;
+		assert!(untraced.hillclimb_extend_run(10_000));
+		assert_eq!(untraced.board, state.board);
+	}
+}
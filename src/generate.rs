@@ -0,0 +1,479 @@
+use super::{seed_rng, KingsWalkError, RandomRestart, State, RNG};
+use rand::rngs::StdRng;
+use rand::seq::{IteratorRandom, SliceRandom};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::collections::HashSet;
+
+// How many times estimate_difficulty solves the puzzle, and the most
+// restarts each try gets
+pub(crate) const DIFFICULTY_TRIALS: usize = 16;
+const DIFFICULTY_CAP: usize = 1_000;
+
+// How hard a puzzle is for hillclimbing, from classify
+#[derive(
+	Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash,
+)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
+pub enum Difficulty {
+	// solved from the first random start, on average
+	Easy,
+	// needs fewer than 8 random starts on average
+	Medium,
+	// needs 8 or more
+	Hard,
+}
+
+// A puzzle made by walking the board from a random cell, turning the
+// walk by a random one of the 8 symmetries of the square and maybe
+// running it backwards, and then blanking blanks random cells of it
+pub fn random_puzzle(
+	n: usize,
+	blanks: usize,
+) -> Result<State, KingsWalkError> {
+	let (walk, t, backwards) = RNG.with(|rng_cell| {
+		let rng = &mut *rng_cell.borrow_mut();
+		let walk = walk_from_random_cell(n, rng)?;
+		Ok((walk, rng.gen_range(0, 8), rng.gen::<bool>()))
+	})?;
+	let mut board = State::new(walk, n)?.transformed(t).into_board();
+	if backwards {
+		let last = board.len() + 1;
+		for value in &mut board {
+			*value = (last - *value as usize) as u8;
+		}
+	}
+	RNG.with(|rng_cell| {
+		blank_cells(&mut board, blanks, &mut *rng_cell.borrow_mut())
+	});
+	State::new(board, n)
+}
+
+// A walk over the board of side n, from a cell picked with rng
+fn walk_from_random_cell(
+	n: usize,
+	rng: &mut impl Rng,
+) -> Result<Vec<u8>, KingsWalkError> {
+	let cells = n * n;
+	let mut board = vec![0; cells];
+	if cells > 0 {
+		board[rng.gen_range(0, cells)] = 1;
+	}
+	let mut solved = State::new(board, n)?;
+	// starting from any cell there is a walk over a king's graph
+	assert!(solved.solve_hamiltonian());
+	Ok(solved.into_board())
+}
+
+// The puzzle made by blanking blanks random cells, or every cell if
+// there are fewer, of a solved board. The seed picks the cells, on a
+// generator of its own. Panics when board is not a board of side n.
+pub fn corrupt(
+	mut board: Vec<u8>,
+	n: usize,
+	blanks: usize,
+	seed: u64,
+) -> State {
+	blank_cells(&mut board, blanks, &mut StdRng::seed_from_u64(seed));
+	State::new(board, n).expect("not a board of side n")
+}
+
+// Set blanks random cells of board, or all of them, to 0
+fn blank_cells(board: &mut [u8], blanks: usize, rng: &mut impl Rng) {
+	for idx in (0..board.len()).choose_multiple(rng, blanks) {
+		board[idx] = 0;
+	}
+}
+
+// Generate attempts random puzzles, each with a random number of
+// blanks, and return the one estimate_difficulty rates hardest. The
+// seed makes the choice reproducible, and the shared random number
+// generator is put back as it was afterwards. Panics when n is not a
+// supported board size.
+pub fn generate_hard(n: usize, attempts: usize, seed: u64) -> State {
+	let saved = RNG.with(|rng_cell| rng_cell.borrow().clone());
+	seed_rng(seed);
+	let mut hardest: Option<(f64, State)> = None;
+	for _ in 0..attempts.max(1) {
+		let blanks = RNG.with(|rng_cell| {
+			rng_cell.borrow_mut().gen_range(0, n * n + 1)
+		});
+		let puzzle = random_puzzle(n, blanks)
+			.expect("n is not a supported size");
+		let difficulty = puzzle.estimate_difficulty();
+		if hardest
+			.as_ref()
+			.is_none_or(|(d, _)| difficulty.total_cmp(d).is_gt())
+		{
+			hardest = Some((difficulty, puzzle));
+		}
+	}
+	RNG.with(|rng_cell| *rng_cell.borrow_mut() = saved);
+	hardest.expect("there is at least one attempt").1
+}
+
+// The puzzle of side n for day day_index, the same on every call and
+// every machine. It walks the board from a cell picked by the day and
+// then blanks cells in an order also picked by the day, keeping each
+// blank only while the puzzle still has a single solution, so the
+// clues left are a minimal set. The day seeds a ChaCha8 generator of
+// its own, whose output is fixed across platforms and versions, and
+// the shared random number generator is left alone. Only practical
+// for small boards, as the exact solver checks each blank. Panics
+// when n is not a supported board size.
+pub fn daily(n: usize, day_index: u64) -> State {
+	let mut rng = ChaCha8Rng::seed_from_u64(day_index);
+	let mut board = walk_from_random_cell(n, &mut rng)
+		.expect("n is not a supported size");
+	let mut cells: Vec<usize> = (0..board.len()).collect();
+	cells.shuffle(&mut rng);
+	for idx in cells {
+		let value = board[idx];
+		board[idx] = 0;
+		let puzzle = State::new(board.clone(), n).unwrap();
+		if puzzle.solutions_capped(2).len() != 1 {
+			board[idx] = value;
+		}
+	}
+	State::new(board, n).unwrap()
+}
+
+// count puzzles of side n with a single solution which classify
+// rates difficulty, generated from seed, with the shared random
+// number generator put back as it was afterwards. Each has a random
+// number of blanks, and no puzzle appears twice. Keeps going until it
+// has count of them, so it never returns when there aren't that many.
+// Only practical for small boards, as checking for a single solution
+// uses the exact solver. Panics when n is not a supported board size.
+pub fn generate_pack(
+	n: usize,
+	difficulty: Difficulty,
+	count: usize,
+	seed: u64,
+) -> Vec<State> {
+	let saved = RNG.with(|rng_cell| rng_cell.borrow().clone());
+	seed_rng(seed);
+	let mut pack = Vec::with_capacity(count);
+	let mut seen = HashSet::new();
+	while pack.len() < count {
+		let blanks = RNG.with(|rng_cell| {
+			rng_cell.borrow_mut().gen_range(0, n * n + 1)
+		});
+		let puzzle = random_puzzle(n, blanks)
+			.expect("n is not a supported size");
+		if puzzle.solutions_capped(2).len() == 1
+			&& puzzle.classify() == difficulty
+			&& seen.insert(puzzle.to_puzzle_string())
+		{
+			pack.push(puzzle);
+		}
+	}
+	RNG.with(|rng_cell| *rng_cell.borrow_mut() = saved);
+	pack
+}
+
+impl State {
+	// Rate the puzzle by the average number of random starts
+	// hillclimbing needs to solve it, the way estimate_difficulty
+	// counts them. The starts come from fixed seeds so a puzzle is
+	// always rated the same, and the shared random number generator
+	// is put back as it was afterwards.
+	pub fn classify(&self) -> Difficulty {
+		let average = self.estimate_difficulty_seeded(0);
+		if average <= 1.0 {
+			Difficulty::Easy
+		} else if average < 8.0 {
+			Difficulty::Medium
+		} else {
+			Difficulty::Hard
+		}
+	}
+	// The average number of random restarts hillclimbing needs to
+	// solve the puzzle, over DIFFICULTY_TRIALS tries. A try that is
+	// still unsolved after DIFFICULTY_CAP restarts counts as the cap.
+	// This is an estimate and varies from call to call.
+	pub fn estimate_difficulty(&self) -> f64 {
+		let total: usize = (0..DIFFICULTY_TRIALS)
+			.map(|_| self.restarts_to_solve())
+			.sum();
+		total as f64 / DIFFICULTY_TRIALS as f64
+	}
+	// estimate_difficulty, with each try's random starts drawn from a
+	// generator seeded from seed and the try's number, so the same
+	// seed always gives the same estimate. The shared random number
+	// generator is put back as it was afterwards.
+	pub fn estimate_difficulty_seeded(&self, seed: u64) -> f64 {
+		let saved = RNG.with(|rng_cell| rng_cell.borrow().clone());
+		let total: usize = (0..DIFFICULTY_TRIALS)
+			.map(|trial| self.trial_restarts(seed, trial))
+			.sum();
+		RNG.with(|rng_cell| *rng_cell.borrow_mut() = saved);
+		total as f64 / DIFFICULTY_TRIALS as f64
+	}
+	// The fraction of samples tries, each hillclimbing a copy of the
+	// puzzle with up to restarts random restarts, that solved it: an
+	// estimate of the chance restarts restarts are enough. Each try's
+	// starts are drawn from a generator seeded from seed and the
+	// try's number, and the shared random number generator is put
+	// back as it was afterwards. 0 when samples is 0.
+	pub fn success_probability(
+		&self,
+		restarts: usize,
+		samples: usize,
+		seed: u64,
+	) -> f64 {
+		if samples == 0 {
+			return 0.0;
+		}
+		let saved = RNG.with(|rng_cell| rng_cell.borrow().clone());
+		let solved = (0..samples)
+			.filter(|&sample| {
+				seed_rng(
+					seed.wrapping_mul(samples as u64)
+						.wrapping_add(sample as u64),
+				);
+				self.clone()
+					.hillclimb_with_policy(&RandomRestart, restarts)
+			})
+			.count();
+		RNG.with(|rng_cell| *rng_cell.borrow_mut() = saved);
+		solved as f64 / samples as f64
+	}
+	// The fewest restarts, doubling from 1 up to max_restarts, that
+	// success_probability with samples tries says solve the puzzle
+	// with at least the chance target, or max_restarts if none do.
+	// A budget for hillclimb_with_policy that shouldn't give up early
+	// on a puzzle that can be solved.
+	pub fn suggested_restarts(
+		&self,
+		target: f64,
+		max_restarts: usize,
+		samples: usize,
+		seed: u64,
+	) -> usize {
+		let mut restarts = 1;
+		while restarts < max_restarts {
+			if self.success_probability(restarts, samples, seed)
+				>= target
+			{
+				return restarts;
+			}
+			restarts = restarts.saturating_mul(2);
+		}
+		max_restarts
+	}
+	// restarts_to_solve for try number trial of a seeded estimate,
+	// which reseeds this thread's generator
+	pub(crate) fn trial_restarts(
+		&self,
+		seed: u64,
+		trial: usize,
+	) -> usize {
+		seed_rng(
+			seed.wrapping_mul(DIFFICULTY_TRIALS as u64)
+				.wrapping_add(trial as u64),
+		);
+		self.restarts_to_solve()
+	}
+	// How many random starts a copy of the puzzle took to climb to a
+	// solution, or DIFFICULTY_CAP if none of that many did
+	fn restarts_to_solve(&self) -> usize {
+		let mut state = self.clone();
+		let mut restarts = 0;
+		while restarts < DIFFICULTY_CAP {
+			restarts += 1;
+			let start_score = state.random_start();
+			if state.climb(start_score, usize::MAX)
+				== state.max_score()
+			{
+				break;
+			}
+		}
+		restarts
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn random_puzzle_should_be_solvable() {
+		for n in 1..=6 {
+			let puzzle = random_puzzle(n, n).unwrap();
+			assert_eq!(puzzle.assignments.len(), n);
+			assert!(puzzle.clues_reachable());
+		}
+	}
+	#[test]
+	fn random_puzzle_should_find_more_walks_than_start_cells() {
+		seed_rng(3);
+		let walks: HashSet<Vec<u8>> = (0..400)
+			.map(|_| random_puzzle(3, 0).unwrap().into_board())
+			.collect();
+		assert!(walks.len() > 9);
+		for walk in walks {
+			assert!(State::new(walk, 3).unwrap().is_solved());
+		}
+	}
+	#[test]
+	fn corrupt_should_blank_exactly_blanks_cells() {
+		let solved = vec![1, 2, 3, 6, 5, 4, 7, 8, 9];
+		for blanks in 0..=9 {
+			let puzzle =
+				corrupt(solved.clone(), 3, blanks, blanks as u64);
+			assert_eq!(puzzle.assignments.len(), blanks);
+			assert!(puzzle.clone().solve_exact());
+			// the same seed blanks the same cells
+			assert_eq!(
+				corrupt(solved.clone(), 3, blanks, blanks as u64)
+					.to_puzzle_string(),
+				puzzle.to_puzzle_string()
+			);
+		}
+		assert_eq!(corrupt(solved, 3, 20, 0).assignments.len(), 9);
+	}
+	#[test]
+	fn generate_pack_should_only_keep_the_difficulty() {
+		let draw = || {
+			RNG.with(|rng_cell| rng_cell.borrow_mut().gen::<u64>())
+		};
+		seed_rng(5);
+		let expected = draw();
+		seed_rng(5);
+		let pack = generate_pack(4, Difficulty::Easy, 5, 3);
+		// the generator carries on as if it wasn't called
+		assert_eq!(draw(), expected);
+		assert_eq!(pack.len(), 5);
+		let mut seen = HashSet::new();
+		for puzzle in pack {
+			assert_eq!(puzzle.classify(), Difficulty::Easy);
+			assert_eq!(puzzle.solutions_capped(2).len(), 1);
+			assert!(puzzle.clone().solve_exact());
+			assert!(seen.insert(puzzle.to_puzzle_string()));
+		}
+	}
+	#[test]
+	fn classify_should_leave_the_random_numbers_alone() {
+		let puzzle = random_puzzle(4, 8).unwrap();
+		let draw = || {
+			RNG.with(|rng_cell| rng_cell.borrow_mut().gen::<u64>())
+		};
+		seed_rng(5);
+		let expected = draw();
+		seed_rng(5);
+		let first = puzzle.classify();
+		assert_eq!(draw(), expected);
+		// and always rates the same
+		assert_eq!(puzzle.classify(), first);
+	}
+	// a 4x4 puzzle few climbs solve
+	fn hard_puzzle() -> State {
+		State::new(
+			vec![0, 0, 0, 0, 0, 3, 4, 0, 0, 0, 0, 0, 12, 0, 0, 0],
+			4,
+		)
+		.unwrap()
+	}
+	#[test]
+	fn success_probability_should_rate_easy_puzzles_higher() {
+		let easy =
+			State::new(vec![0, 0, 1, 0, 2, 0, 9, 0, 0], 3).unwrap();
+		assert_eq!(easy.success_probability(100, 20, 1), 1.0);
+		let hard = hard_puzzle();
+		let chance = hard.success_probability(1, 20, 1);
+		assert!(chance < 1.0);
+		assert_eq!(hard.success_probability(1, 20, 1), chance);
+		assert!(hard.success_probability(100, 20, 1) > chance);
+		// 1 and 2 are fixed in opposite corners
+		let none =
+			State::new(vec![1, 0, 0, 0, 0, 0, 0, 0, 2], 3).unwrap();
+		assert_eq!(none.success_probability(10, 20, 1), 0.0);
+		assert_eq!(easy.success_probability(10, 0, 1), 0.0);
+	}
+	#[test]
+	fn suggested_restarts_should_grow_with_difficulty() {
+		let easy =
+			State::new(vec![0, 0, 1, 0, 2, 0, 9, 0, 0], 3).unwrap();
+		let hard = hard_puzzle();
+		let easy_budget = easy.suggested_restarts(0.9, 1_000, 10, 2);
+		let hard_budget = hard.suggested_restarts(0.9, 1_000, 10, 2);
+		assert!(easy_budget < hard_budget);
+		assert!(hard.success_probability(hard_budget, 10, 2) >= 0.9);
+		let none =
+			State::new(vec![1, 0, 0, 0, 0, 0, 0, 0, 2], 3).unwrap();
+		assert_eq!(none.suggested_restarts(0.5, 16, 4, 2), 16);
+	}
+	#[test]
+	fn daily_should_be_the_same_every_call() {
+		let puzzle = daily(5, 42);
+		seed_rng(7);
+		assert_eq!(
+			daily(5, 42).to_puzzle_string(),
+			puzzle.to_puzzle_string()
+		);
+		assert_ne!(
+			daily(5, 43).to_puzzle_string(),
+			puzzle.to_puzzle_string()
+		);
+		assert_eq!(puzzle.solutions_capped(2).len(), 1);
+		assert!(puzzle.clone().solve_exact());
+		// the shared generator carries on as if daily wasn't called
+		let draw = || {
+			RNG.with(|rng_cell| rng_cell.borrow_mut().gen::<u64>())
+		};
+		seed_rng(7);
+		let expected = draw();
+		seed_rng(7);
+		daily(4, 1);
+		assert_eq!(draw(), expected);
+		// pinned, as the day's puzzle must not change between builds
+		assert_eq!(
+			daily(4, 1).to_puzzle_string(),
+			". 15 . 2\n. 16 1 .\n11 . 8 .\n. . . 6\n"
+		);
+		// no clue can go without a second solution appearing
+		for idx in 0..25 {
+			if !puzzle.is_free(idx) {
+				let mut board = puzzle.board.clone();
+				for &free in &puzzle.assignments {
+					board[free] = 0;
+				}
+				board[idx] = 0;
+				let fewer = State::new(board, 5).unwrap();
+				assert_eq!(fewer.solutions_capped(2).len(), 2);
+			}
+		}
+	}
+	#[test]
+	fn generate_hard_should_beat_an_average_puzzle() {
+		let draw = || {
+			RNG.with(|rng_cell| rng_cell.borrow_mut().gen::<u64>())
+		};
+		seed_rng(5);
+		let expected = draw();
+		seed_rng(5);
+		let hard = generate_hard(3, 20, 11);
+		// the generator carries on as if it wasn't called
+		assert_eq!(draw(), expected);
+		assert_eq!(
+			generate_hard(3, 20, 11).to_puzzle_string(),
+			hard.to_puzzle_string()
+		);
+		assert!(hard.clone().solve_exact());
+		// rate it against random puzzles on the same footing
+		seed_rng(12);
+		let average = (0..20)
+			.map(|_| {
+				let blanks = RNG.with(|rng_cell| {
+					rng_cell.borrow_mut().gen_range(0, 10)
+				});
+				random_puzzle(3, blanks)
+					.unwrap()
+					.estimate_difficulty()
+			})
+			.sum::<f64>()
+			/ 20.0;
+		assert!(hard.estimate_difficulty() > average);
+	}
+}
@@ -0,0 +1,117 @@
+use super::State;
+
+impl State {
+	// Solve by finding a Hamiltonian path through the king adjacency
+	// graph, where the path visits the cell holding k at step k.
+	// Fixed values pin the path to their cells, and among the free
+	// cells the one with the fewest unvisited neighbors is tried
+	// first (Warnsdorff's rule), backtracking on dead ends. Returns
+	// whether a solution exists, in which case the board holds it.
+	pub fn solve_hamiltonian(&mut self) -> bool {
+		let adjacency = self.adjacency_list();
+		let (fixed, mut used) = self.walk_clues();
+		let mut path = Vec::with_capacity(self.board.len());
+		if !self.extend_path(&adjacency, &fixed, &mut used, &mut path)
+		{
+			return false;
+		}
+		self.apply_walk(&path);
+		true
+	}
+	// Try to continue path to the next step
+	fn extend_path(
+		&self,
+		adjacency: &[Vec<usize>],
+		fixed: &[Option<usize>],
+		used: &mut [bool],
+		path: &mut Vec<usize>,
+	) -> bool {
+		let value = path.len() + 1;
+		if value == fixed.len() {
+			return self.walk_meets_constraints(path);
+		}
+		let mut candidates = match path.last() {
+			Some(&prev) if self.unbroken(value - 1, value) => {
+				adjacency[prev].clone()
+			}
+			_ => (0..self.board.len()).collect(),
+		};
+		if let Some(idx) = fixed[value] {
+			// the path has to pass through the fixed value's cell
+			candidates.retain(|&c| c == idx);
+		} else {
+			// the next fixed value must still be in reach
+			let next_fixed = (value + 1..fixed.len())
+				.find_map(|v| fixed[v].map(|idx| (v, idx)))
+				.filter(|&(v, _)| self.unbroken(value, v));
+			candidates.retain(|&c| {
+				!used[c]
+					&& self.allows(c, value as u8)
+					&& next_fixed.is_none_or(|(v, target)| {
+						self.king_distance(c, target) <= v - value
+					})
+			});
+		}
+		// Warnsdorff's rule: the most cornered cells go first
+		candidates.sort_by_key(|&c| {
+			adjacency[c].iter().filter(|&&x| !used[x]).count()
+		});
+		for idx in candidates {
+			// fixed cells are always marked used
+			let free = !used[idx];
+			used[idx] = true;
+			path.push(idx);
+			if self.extend_path(adjacency, fixed, used, path) {
+				return true;
+			}
+			path.pop();
+			if free {
+				used[idx] = false;
+			}
+		}
+		false
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// solve_hamiltonian must land on one of the solutions the
+	// enumerator finds
+	fn matches_solutions(board: Vec<u8>, n: usize) {
+		let mut state = State::new(board, n).unwrap();
+		let solutions: Vec<Vec<u8>> = state
+			.solutions()
+			.into_iter()
+			.map(State::into_board)
+			.collect();
+		assert!(state.solve_hamiltonian());
+		assert_eq!(state.score(), state.max_score());
+		assert!(solutions.contains(&state.board));
+	}
+	#[test]
+	fn solve_hamiltonian_should_solve_n_eq_3() {
+		matches_solutions(vec![0, 0, 1, 0, 2, 0, 9, 0, 0], 3);
+	}
+	#[test]
+	fn solve_hamiltonian_should_solve_n_eq_4() {
+		matches_solutions(
+			vec![0, 0, 0, 0, 0, 3, 4, 0, 0, 0, 0, 0, 12, 0, 0, 0],
+			4,
+		);
+		matches_solutions(
+			vec![9, 8, 7, 6, 0, 3, 4, 0, 0, 0, 0, 0, 12, 0, 0, 0],
+			4,
+		);
+	}
+	#[test]
+	fn solve_hamiltonian_should_report_unsolvable() {
+		// 1 and 2 are fixed in opposite corners
+		let mut state =
+			State::new(vec![1, 0, 0, 0, 0, 0, 0, 0, 2], 3).unwrap();
+		let before = state.board.clone();
+		assert!(!state.solve_hamiltonian());
+		assert_eq!(before, state.board);
+	}
+}
@@ -0,0 +1,3267 @@
+// Only the nightly feature may use unstable features, so everything
+// else builds on a stable toolchain
+#![cfg_attr(not(feature = "nightly"), forbid(unstable_features))]
+#![cfg_attr(all(test, feature = "nightly"), feature(test))]
+#![allow(dead_code)]
+extern crate thiserror;
+use thiserror::Error;
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::mem::size_of;
+use std::str::FromStr;
+use std::sync::mpsc::{self, Receiver};
+use std::thread::{self, JoinHandle};
+
+mod anneal;
+#[cfg(feature = "ndarray")]
+mod array;
+mod bakeoff;
+mod batch;
+mod beam;
+mod builder;
+mod bytes;
+#[cfg(feature = "serde")]
+mod catalog;
+mod check;
+mod cnf;
+mod display;
+mod exact;
+mod extend;
+mod generate;
+mod hamiltonian;
+mod links;
+mod order;
+#[cfg(feature = "rayon")]
+mod parallel;
+pub mod prelude;
+#[cfg(feature = "png")]
+mod render;
+mod restart;
+mod sample;
+mod scored;
+mod symmetry;
+mod topology;
+pub use bakeoff::{bakeoff, Strategy};
+pub use batch::solve_batch;
+pub use builder::SolverBuilder;
+#[cfg(feature = "serde")]
+pub use catalog::{load_catalog, PuzzleEntry};
+pub use check::check_solution;
+pub use display::{DisplayConfig, Displayed, ScoreLine};
+pub use generate::{
+	corrupt, daily, generate_hard, generate_pack, random_puzzle,
+	Difficulty,
+};
+pub use links::solve_from_adjacency;
+pub use order::AssignmentOrder;
+#[cfg(feature = "rayon")]
+pub use parallel::solve_batch_parallel;
+pub use restart::{
+	luby, GreedyRestart, LubyRestart, PerturbRestart, RandomRestart,
+	RestartPolicy,
+};
+pub use sample::score_histogram;
+pub use scored::ScoredState;
+pub use topology::Topology;
+
+// The largest n a board of u8 values supports, since every value in
+// [1,n*n] has to fit in a u8
+pub const MAX_N_U8: usize = 15;
+
+#[derive(Error, Debug, PartialEq, Clone, Copy)]
+pub enum KingsWalkError {
+	#[error("The board length must be n*n.")]
+	BoardLength,
+	#[error("n can be at most {}.", MAX_N_U8)]
+	BoardTooLarge,
+	#[error("The value at cell {0} is not in [1,n*n].")]
+	ValueOutOfRange(usize),
+	#[error("The value at cell {0} appears more than once.")]
+	DuplicateValue(usize),
+	#[error("Cell {0} is not a number.")]
+	InvalidCell(usize),
+	#[error("Cell {0} is fixed.")]
+	FixedCell(usize),
+	#[error("Row {0} is not on the board.")]
+	InvalidRow(usize),
+	#[error("({0}, {1}) is not on the board.")]
+	InvalidPosition(usize, usize),
+	#[error("The blank {0} is also a value on the board.")]
+	InvalidBlank(u8),
+	#[error("The break {0} is not in [1,n*n-1].")]
+	InvalidBreak(u8),
+	#[error("The board is not solved.")]
+	NotSolved,
+	#[error("The value at cell {0} is not the puzzle's clue.")]
+	ClueMismatch(usize),
+	#[error("The catalog is not a list of puzzles, at line {0}.")]
+	InvalidCatalog(usize),
+	#[error("Step {0} of the path is not a king move.")]
+	InvalidStep(usize),
+}
+
+// Every random choice is drawn from this per-thread generator, never
+// from anything a State owns, so a State is Send and Sync and can be
+// moved to or shared with other threads freely. The methods drawing
+// from it are random_start, biased_restart, anneal and the restart
+// policies, along with everything built on them: hillclimb and its
+// variants, SolverBuilder, auto_solve, is_solvable, solve_explained,
+// min_defects, minimize_clues, update_clue, estimate_difficulty,
+// random_puzzle, score_histogram and bakeoff. classify,
+// estimate_difficulty_seeded, success_probability,
+// suggested_restarts, hillclimb_seeds, generate_hard, generate_pack
+// and par_estimate_difficulty draw from it too, but put it back as
+// it was, and daily draws from a generator of its own. Work sent to
+// another thread, as solve_with_progress and solve_batch_parallel do,
+// draws from that thread's generator, which starts from entropy.
+// par_step and the exact solvers draw nothing.
+thread_local! {
+	static RNG: RefCell<StdRng> = RefCell::new(StdRng::from_entropy());
+}
+
+// Reseed this thread's random number generator so that the solves
+// which follow on it are reproducible
+pub fn seed_rng(seed: u64) {
+	RNG.with(|rng_cell| {
+		*rng_cell.borrow_mut() = StdRng::seed_from_u64(seed)
+	});
+}
+
+// How step picks among the swaps that raise the score
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepMode {
+	// The swap raising the score the most (steepest ascent)
+	BestImprovement,
+	// The first swap found that raises the score at all
+	FirstImprovement,
+}
+
+// What a call to step_budget got done
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepResult {
+	// The budget ran out, and the next call carries on the scan
+	Pending,
+	// The scan finished and made the best swap, if any raised the
+	// score, which is now the given score
+	Done(usize),
+}
+
+// The (row, col) at each end of a link, as link_status gives them
+type Segment = ((usize, usize), (usize, usize));
+
+// How far a scan of the candidate swaps has got, and the best swap
+// it found so far
+#[derive(Debug, Clone)]
+struct SwapScan {
+	prev: usize,
+	next: usize,
+	start_score: usize,
+	high_score: usize,
+	best: Option<(usize, usize, usize)>,
+}
+
+impl SwapScan {
+	fn new(start_score: usize) -> SwapScan {
+		SwapScan {
+			prev: 0,
+			next: 1,
+			start_score,
+			high_score: start_score,
+			best: None,
+		}
+	}
+}
+
+// Holds the filled out game board which is a [1,n*n] permutation and
+// a vec of indicies which to the board that are mutable, kept in
+// the order order gives, row by row unless reorder_assignments says
+// otherwise. pos is the inverse of the board, the index each value
+// sits at. Constraints are extra pairs of values that must also end
+// up adjacent. Breaks are the values after which the walk may jump,
+// sorted. Restrictions map free cells to the only values they may
+// hold. scan is where step_budget left off, along with the board it
+// was scanning.
+#[derive(Debug, Clone)]
+pub struct State {
+	board: Vec<u8>,
+	pos: Vec<usize>,
+	n: usize,
+	assignments: Vec<usize>,
+	constraints: Vec<(u8, u8)>,
+	breaks: Vec<u8>,
+	restrictions: BTreeMap<usize, Vec<u8>>,
+	scan: Option<(Vec<u8>, SwapScan)>,
+	topology: Topology,
+	order: AssignmentOrder,
+}
+
+// Pretty printing of the board
+impl fmt::Display for State {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		self.display(DisplayConfig::default()).fmt(f)
+	}
+}
+
+// The board, one value per cell in row order
+impl AsRef<[u8]> for State {
+	fn as_ref(&self) -> &[u8] {
+		&self.board
+	}
+}
+
+// The puzzle the state started as, in row order, with None for each
+// free cell and the value of each fixed one
+impl From<&State> for Vec<Option<u8>> {
+	fn from(state: &State) -> Vec<Option<u8>> {
+		let mut cells: Vec<Option<u8>> =
+			state.board.iter().copied().map(Some).collect();
+		for &idx in &state.assignments {
+			cells[idx] = None;
+		}
+		cells
+	}
+}
+
+// Parse a board written one row per line, with the values separated
+// by whitespace and 0 or . marking the free cells. Empty lines and
+// lines starting with # are skipped.
+impl FromStr for State {
+	type Err = KingsWalkError;
+	fn from_str(s: &str) -> Result<State, KingsWalkError> {
+		State::parse_with_blank(s, 0)
+	}
+}
+
+// Warn on stderr about a state dropped with a board that isn't a
+// permutation, a sign that something corrupted it along the way
+#[cfg(all(feature = "debug-invariants", debug_assertions))]
+impl Drop for State {
+	fn drop(&mut self) {
+		if !self.board_is_permutation() {
+			#[cfg(test)]
+			tests::INVALID_DROPS.with(|c| c.set(c.get() + 1));
+			eprintln!(
+				"warning: dropped a state whose board isn't a \
+				 permutation: {:?}",
+				self.board
+			);
+		}
+	}
+}
+
+// An empty 3x3 puzzle
+impl Default for State {
+	fn default() -> State {
+		State::new_empty(3).unwrap()
+	}
+}
+
+impl State {
+	// Write the puzzle in the format FromStr reads, with . for each
+	// free cell
+	pub fn to_puzzle_string(&self) -> String {
+		let mut free = vec![false; self.board.len()];
+		for &idx in &self.assignments {
+			free[idx] = true;
+		}
+		let mut out = String::new();
+		for (row, values) in self.board.chunks(self.n).enumerate() {
+			let cells: Vec<String> = values
+				.iter()
+				.enumerate()
+				.map(|(col, v)| {
+					if free[row * self.n + col] {
+						".".to_string()
+					} else {
+						v.to_string()
+					}
+				})
+				.collect();
+			out.push_str(&cells.join(" "));
+			out.push('\n');
+		}
+		out
+	}
+}
+
+// score as a fraction of max_score. A 1x1 board has no links to make
+// and is always complete.
+fn fraction(score: usize, max_score: usize) -> f64 {
+	if max_score == 0 {
+		1.0
+	} else {
+		score as f64 / max_score as f64
+	}
+}
+
+impl State {
+	// Create a new state object from a board where blank marks the
+	// free cells instead of 0. The blank can't be a value in [1,n*n].
+	pub fn with_blank(
+		mut board: Vec<u8>,
+		n: usize,
+		blank: u8,
+	) -> Result<State, KingsWalkError> {
+		if blank != 0 {
+			if (1..=board.len()).contains(&(blank as usize)) {
+				return Err(KingsWalkError::InvalidBlank(blank));
+			}
+			for (idx, value) in board.iter_mut().enumerate() {
+				match *value {
+					// 0 is only a blank when nothing else is
+					0 => {
+						return Err(KingsWalkError::ValueOutOfRange(
+							idx,
+						))
+					}
+					v if v == blank => *value = 0,
+					_ => {}
+				}
+			}
+		}
+		State::new(board, n)
+	}
+	// Parse a board the way FromStr does, but with blank marking the
+	// free cells instead of 0. A . is still a free cell.
+	pub fn parse_with_blank(
+		s: &str,
+		blank: u8,
+	) -> Result<State, KingsWalkError> {
+		let rows: Vec<&str> = s
+			.lines()
+			.map(str::trim)
+			.filter(|line| !line.is_empty() && !line.starts_with('#'))
+			.collect();
+		// the board is square so there are as many columns as rows
+		let n = rows.len();
+		let mut board = Vec::new();
+		for row in rows {
+			// a ragged row is reported before any cell in it, so
+			// the index of a bad cell is always row * n + col
+			let tokens: Vec<&str> = row.split_whitespace().collect();
+			if tokens.len() != n {
+				return Err(KingsWalkError::BoardLength);
+			}
+			for token in tokens {
+				let value = match token {
+					"." => blank,
+					_ => token.parse().map_err(|_| {
+						KingsWalkError::InvalidCell(board.len())
+					})?,
+				};
+				board.push(value);
+			}
+		}
+		State::with_blank(board, n, blank)
+	}
+	// Create a new state object
+	pub fn new(
+		board: Vec<u8>,
+		n: usize,
+	) -> Result<State, KingsWalkError> {
+		if n > MAX_N_U8 {
+			return Err(KingsWalkError::BoardTooLarge);
+		}
+		if n == 0 || board.len() != n * n {
+			return Err(KingsWalkError::BoardLength);
+		}
+		// Identify the mutable positions of the board and determine
+		// what values are taken.
+		let mut assignments = Vec::new();
+		let mut seen = vec![false; n * n + 1];
+		for (idx, &start_value) in board.iter().enumerate() {
+			if start_value == 0 {
+				// Save the position index
+				assignments.push(idx);
+			} else if start_value as usize > n * n {
+				return Err(KingsWalkError::ValueOutOfRange(idx));
+			} else if seen[start_value as usize] {
+				return Err(KingsWalkError::DuplicateValue(idx));
+			}
+			// Record the seen value
+			seen[start_value as usize] = true;
+		}
+		// only build the state once the board is known to be good,
+		// so a rejected board is never dropped as a state
+		let mut state = State {
+			board,
+			pos: Vec::new(),
+			n,
+			assignments,
+			constraints: Vec::new(),
+			breaks: Vec::new(),
+			restrictions: BTreeMap::new(),
+			scan: None,
+			topology: Topology::Square,
+			order: AssignmentOrder::RowMajor,
+		};
+		// Assign the remaining values by overwriting the zeros
+		let mut next_unseen = 1;
+		for idx in &state.assignments {
+			// Increment the next_unseen pointer until it points to
+			// something that is unassigned
+			while seen[next_unseen] {
+				next_unseen += 1;
+			}
+			// Assign this position on the board to the unusued value
+			state.board[*idx] = next_unseen as u8;
+			// Ensure to move to the next position.
+			next_unseen += 1;
+		}
+		state.board.shrink_to_fit();
+		state.assignments.shrink_to_fit();
+		state.index_positions();
+		debug_assert!(state.assignments_ordered());
+		Ok(state)
+	}
+	// Create a puzzle whose walk is split into separate runs: after
+	// each value in breaks the walk may jump to any cell, so the link
+	// from it to the next value neither counts nor is required
+	pub fn with_segments(
+		board: Vec<u8>,
+		n: usize,
+		breaks: &[u8],
+	) -> Result<State, KingsWalkError> {
+		let mut state = State::new(board, n)?;
+		for &b in breaks {
+			if b == 0 || b as usize >= n * n {
+				return Err(KingsWalkError::InvalidBreak(b));
+			}
+		}
+		state.breaks = breaks.to_vec();
+		state.breaks.sort_unstable();
+		state.breaks.dedup();
+		Ok(state)
+	}
+	// Create a new state object from the cells in row order. Stops
+	// reading one cell past n*n, which is enough to tell the board is
+	// too long.
+	pub fn from_iter_n<I: IntoIterator<Item = u8>>(
+		iter: I,
+		n: usize,
+	) -> Result<State, KingsWalkError> {
+		if n > MAX_N_U8 {
+			return Err(KingsWalkError::BoardTooLarge);
+		}
+		State::new(iter.into_iter().take(n * n + 1).collect(), n)
+	}
+	// Create a new state object from the cells in row order, with
+	// None for each free cell. A Some(0) is out of range rather than
+	// free.
+	pub fn from_options(
+		cells: Vec<Option<u8>>,
+		n: usize,
+	) -> Result<State, KingsWalkError> {
+		if let Some(idx) =
+			cells.iter().position(|&cell| cell == Some(0))
+		{
+			return Err(KingsWalkError::ValueOutOfRange(idx));
+		}
+		State::new(
+			cells.into_iter().map(|cell| cell.unwrap_or(0)).collect(),
+			n,
+		)
+	}
+	// Create a puzzle of side n with every cell free
+	pub fn new_empty(n: usize) -> Result<State, KingsWalkError> {
+		if n > MAX_N_U8 {
+			return Err(KingsWalkError::BoardTooLarge);
+		}
+		State::new(vec![0; n * n], n)
+	}
+	// The side length of the board
+	pub fn dimension(&self) -> usize {
+		self.n
+	}
+	// The fraction of the cells which are free
+	pub fn blank_density(&self) -> f64 {
+		self.assignments.len() as f64 / self.board.len() as f64
+	}
+	// An estimate of the bytes held by this state: the struct itself
+	// plus the heap capacity of the board, position index,
+	// assignments, links, restrictions and any paused swap scan
+	pub fn memory_footprint(&self) -> usize {
+		let restrictions: usize = self
+			.restrictions
+			.values()
+			.map(|allowed| {
+				size_of::<usize>()
+					+ size_of::<Vec<u8>>()
+					+ allowed.capacity()
+			})
+			.sum();
+		let scan = self
+			.scan
+			.as_ref()
+			.map_or(0, |(board, _)| board.capacity());
+		size_of::<State>()
+			+ self.board.capacity()
+			+ self.pos.capacity() * size_of::<usize>()
+			+ self.assignments.capacity() * size_of::<usize>()
+			+ self.constraints.capacity() * size_of::<(u8, u8)>()
+			+ self.breaks.capacity()
+			+ restrictions
+			+ scan
+	}
+	// Put value in the free cell at (row, col), moving the value
+	// that was there to where value came from. Both cells must be
+	// free.
+	pub fn try_place(
+		&mut self,
+		row: usize,
+		col: usize,
+		value: u8,
+	) -> Result<(), KingsWalkError> {
+		if row >= self.n || col >= self.n {
+			return Err(KingsWalkError::InvalidPosition(row, col));
+		}
+		let idx = row * self.n + col;
+		if value == 0 || value as usize > self.board.len() {
+			return Err(KingsWalkError::ValueOutOfRange(idx));
+		}
+		let from = self.pos[value as usize];
+		for &cell in &[idx, from] {
+			if !self.is_free(cell) {
+				return Err(KingsWalkError::FixedCell(cell));
+			}
+		}
+		self.swap(idx, from);
+		Ok(())
+	}
+	// Fix 1 at start and n*n at end, so the walk has to run between
+	// them. Both cells must be free and distinct, and neither value
+	// may already be fixed.
+	pub fn require_endpoints(
+		&mut self,
+		start: (usize, usize),
+		end: (usize, usize),
+	) -> Result<(), KingsWalkError> {
+		for &(row, col) in &[start, end] {
+			if row >= self.n || col >= self.n {
+				return Err(KingsWalkError::InvalidPosition(
+					row, col,
+				));
+			}
+		}
+		let last = self.board.len() as u8;
+		let (first_idx, last_idx) =
+			(start.0 * self.n + start.1, end.0 * self.n + end.1);
+		if first_idx == last_idx {
+			return Err(KingsWalkError::DuplicateValue(last_idx));
+		}
+		// the cells and the values' current cells must all be free
+		let cells = [
+			first_idx,
+			last_idx,
+			self.pos[1],
+			self.pos[last as usize],
+		];
+		for &idx in &cells {
+			if !self.is_free(idx) {
+				return Err(KingsWalkError::FixedCell(idx));
+			}
+		}
+		self.swap(first_idx, self.pos[1]);
+		self.swap(last_idx, self.pos[last as usize]);
+		self.assignments
+			.retain(|&idx| idx != first_idx && idx != last_idx);
+		self.apply_order();
+		Ok(())
+	}
+	// Fix the values 1, 2, ... at the (row, col) positions of prefix
+	// in turn, so the walk has to start along it. Each position must
+	// be a king move from the one before, unless a break comes
+	// between, and be free or already hold its value, and each value
+	// must be free or already sit there. Nothing changes if the
+	// prefix is rejected.
+	pub fn with_path_prefix(
+		&mut self,
+		prefix: &[(usize, usize)],
+	) -> Result<(), KingsWalkError> {
+		if prefix.len() > self.board.len() {
+			return Err(KingsWalkError::BoardLength);
+		}
+		let mut cells = Vec::with_capacity(prefix.len());
+		for (step, &(row, col)) in prefix.iter().enumerate() {
+			if row >= self.n || col >= self.n {
+				return Err(KingsWalkError::InvalidPosition(
+					row, col,
+				));
+			}
+			let idx = row * self.n + col;
+			if cells.contains(&idx) {
+				return Err(KingsWalkError::DuplicateValue(idx));
+			}
+			let value = step + 1;
+			if let Some(&prev) = cells.last() {
+				if self.unbroken(value - 1, value)
+					&& self.king_distance(prev, idx) != 1
+				{
+					return Err(KingsWalkError::InvalidStep(step));
+				}
+			}
+			// a clue agreeing with the prefix is fine as it is
+			if self.pos[value] != idx {
+				for &cell in &[idx, self.pos[value]] {
+					if !self.is_free(cell) {
+						return Err(KingsWalkError::FixedCell(cell));
+					}
+				}
+			}
+			cells.push(idx);
+		}
+		for (step, &idx) in cells.iter().enumerate() {
+			self.swap(idx, self.pos[step + 1]);
+		}
+		self.assignments.retain(|idx| !cells.contains(idx));
+		self.apply_order();
+		Ok(())
+	}
+	// Write values into a row. Fixed cells in the row must be given
+	// their own value, and every value has to come from a free cell,
+	// which takes the value that was displaced so the board stays a
+	// permutation. A value held by a fixed cell elsewhere is a
+	// FixedCell error naming that cell, as with place. Nothing
+	// changes if the row is rejected.
+	pub fn set_row(
+		&mut self,
+		row: usize,
+		values: &[u8],
+	) -> Result<(), KingsWalkError> {
+		if row >= self.n {
+			return Err(KingsWalkError::InvalidRow(row));
+		}
+		if values.len() != self.n {
+			return Err(KingsWalkError::BoardLength);
+		}
+		let cells = self.board.len();
+		let mut free = vec![false; cells];
+		for &idx in &self.assignments {
+			free[idx] = true;
+		}
+		let start = row * self.n;
+		let mut placed = vec![false; cells + 1];
+		for (idx, &value) in (start..).zip(values) {
+			if value == 0 || value as usize > cells {
+				return Err(KingsWalkError::ValueOutOfRange(idx));
+			}
+			if placed[value as usize] {
+				return Err(KingsWalkError::DuplicateValue(idx));
+			}
+			placed[value as usize] = true;
+			if !free[idx] && self.board[idx] != value {
+				return Err(KingsWalkError::FixedCell(idx));
+			}
+			// a fixed value can't be moved anywhere else
+			let from = self.pos[value as usize];
+			if free[idx] && !free[from] {
+				return Err(KingsWalkError::FixedCell(from));
+			}
+		}
+		for (idx, &value) in (start..).zip(values) {
+			self.swap(idx, self.pos[value as usize]);
+		}
+		Ok(())
+	}
+	// The puzzle this state started as: a copy with every free cell
+	// blanked and filled in again the way new fills it
+	pub fn to_puzzle(&self) -> State {
+		let mut board = self.board.clone();
+		for &idx in &self.assignments {
+			board[idx] = 0;
+		}
+		let mut puzzle = State::new(board, self.n)
+			.expect("the fixed cells were already validated");
+		puzzle.constraints = self.constraints.clone();
+		puzzle.breaks = self.breaks.clone();
+		puzzle.restrictions = self.restrictions.clone();
+		puzzle.topology = self.topology;
+		puzzle.reorder_assignments(self.order);
+		puzzle
+	}
+	// The value in the cell at (row, col), if it is on the board
+	pub fn value_at(&self, row: usize, col: usize) -> Option<u8> {
+		if row < self.n && col < self.n {
+			Some(self.board[row * self.n + col])
+		} else {
+			None
+		}
+	}
+	// The top left and bottom right (row, col) of the smallest
+	// rectangle holding every fixed cell, or None with no fixed cells
+	pub fn clue_bounding_box(
+		&self,
+	) -> Option<((usize, usize), (usize, usize))> {
+		let mut cells = (0..self.board.len())
+			.filter(|&idx| !self.is_free(idx))
+			.map(|idx| (idx / self.n, idx % self.n));
+		let first = cells.next()?;
+		Some(cells.fold(
+			(first, first),
+			|(top, bottom), (row, col)| {
+				(
+					(top.0.min(row), top.1.min(col)),
+					(bottom.0.max(row), bottom.1.max(col)),
+				)
+			},
+		))
+	}
+	// The (row, col) of the cell holding value, if it is in [1,n*n]
+	pub fn position_of(&self, value: u8) -> Option<(usize, usize)> {
+		let idx =
+			*self.pos.get(value as usize).filter(|_| value != 0)?;
+		Some((idx / self.n, idx % self.n))
+	}
+	// The (row, col) of every free cell
+	pub fn free_cells(
+		&self,
+	) -> impl Iterator<Item = (usize, usize)> + '_ {
+		self.assignments
+			.iter()
+			.map(move |&idx| (idx / self.n, idx % self.n))
+	}
+	// The board, one value per cell in row order, borrowed
+	pub fn board(&self) -> &[u8] {
+		&self.board
+	}
+	// The values in the free cells, in board order whatever order
+	// the free cells are kept in. When every cell is free that is the
+	// board itself, which is borrowed rather than copied.
+	pub fn free_values(&self) -> Cow<'_, [u8]> {
+		if self.assignments.len() == self.board.len() {
+			return Cow::Borrowed(&self.board);
+		}
+		let mut cells = self.assignments.clone();
+		cells.sort_unstable();
+		Cow::Owned(cells.iter().map(|&idx| self.board[idx]).collect())
+	}
+	// The solved board, as the answer key to the puzzle
+	pub fn into_answer_key(self) -> Result<Vec<u8>, KingsWalkError> {
+		if !self.is_solved() {
+			return Err(KingsWalkError::NotSolved);
+		}
+		Ok(self.into_board())
+	}
+	// The free cells' indices in the order of the values they hold,
+	// so filling them in that order follows the walk. Empty unless
+	// the board is solved.
+	pub fn reveal_order(&self) -> Vec<usize> {
+		if !self.is_solved() {
+			return Vec::new();
+		}
+		let mut order = self.assignments.clone();
+		order.sort_unstable_by_key(|&idx| self.board[idx]);
+		order
+	}
+	// The cell holding each value, in value order, which is the walk
+	// once the board is solved
+	pub fn solution_path(&self) -> Vec<usize> {
+		self.pos[1..].to_vec()
+	}
+	// Swap assignments to create a new random start
+	// returns the new score
+	pub fn random_start(&mut self) -> usize {
+		// Create a sequence of (idx1, idx2) so that idx1 can be
+		// swapped with idx2. idx1 will be the position in the array
+		// and idx2 will be the random value stored at that location.
+		// idx2 may be 0 so that a position can keep its value.
+		let swaps: Vec<usize> = RNG.with(|rng_cell| {
+			let mut rng = rng_cell.borrow_mut();
+			(1..self.assignments.len())
+				.rev()
+				.map(|x| rng.gen::<usize>() % (x + 1))
+				.collect()
+		});
+		for (idx1, &idx2) in swaps.iter().enumerate() {
+			self.swap(
+				self.assignments[idx1],
+				self.assignments[idx2 + idx1],
+			);
+		}
+		self.meet_restrictions();
+		debug_assert!(self.positions_consistent());
+		debug_assert!(self.assignments_ordered());
+		self.score()
+	}
+	// Score the board in its current state
+	pub fn score(&self) -> usize {
+		#[inline]
+		// helper function to determine sum of neighbors' scores
+		fn helper(goal: u8, neighbors: &[u8]) -> usize {
+			// the edge cases of 0 and n*n+1 don't matter unless
+			// the board uses all 255 values that an u8 can represent
+			let goal1 = goal + 1;
+			let goal2 = goal - 1;
+			// count without branching, as goal1 and goal2 differ
+			neighbors
+				.iter()
+				.map(|&x| {
+					(x == goal1) as usize + (x == goal2) as usize
+				})
+				.sum()
+		}
+		// other topologies count the links value by value
+		if self.topology != Topology::Square {
+			let links = (1..self.board.len())
+				.filter(|&v| {
+					self.king_distance(self.pos[v], self.pos[v + 1])
+						== 1
+				})
+				.count();
+			return links + self.satisfied_constraints()
+				- self.links_across_breaks();
+		}
+		// Go through every position on the board to determine
+		// it's score
+		let mut total = 0;
+		for (idx, &goal) in self.board.iter().enumerate() {
+			// of all 8 neighbors of each position on the board,
+			// only consider the one to the right and the 3 below
+			// because as we progress through the board, the positions
+			// behind us have already been considered
+			let mut neighbors = vec![idx + self.n];
+			// if youre left aligned, skip bottom left
+			if idx % self.n != 0 {
+				// bottom left
+				neighbors.push(idx + self.n - 1);
+			}
+			// if youre right alined, skip right and bottom right
+			if idx % self.n != self.n - 1 {
+				// to the right
+				neighbors.push(idx + 1);
+				// bottom right
+				neighbors.push(idx + self.n + 1);
+			}
+
+			let mut valid_neighbors: Vec<u8> = Vec::new();
+			for neighbor in neighbors {
+				// If that neighbor position is still on the board,
+				// save it
+				if let Some(&v) = self.board.get(neighbor) {
+					valid_neighbors.push(v);
+				}
+			}
+			// add 0, 1, or 2 depending on how many neighbors were
+			// correctly assigned
+			total += helper(goal, &valid_neighbors);
+		}
+		// add 1 for every constraint whose values are adjacent, and
+		// take back the links across breaks
+		total + self.satisfied_constraints()
+			- self.links_across_breaks()
+	}
+	// The max score is the size of number of edges (verticies - 1)
+	// less the breaks plus one for each constraint. This is the only
+	// place the links a solution needs are counted, so every solver
+	// stops on it, through is_solved or by comparing scores to it.
+	#[inline]
+	pub fn max_score(&self) -> usize {
+		self.board.len() - 1 - self.breaks.len()
+			+ self.constraints.len()
+	}
+	// Whether the board is a solution, with every restricted cell
+	// holding a value it allows
+	pub fn is_solved(&self) -> bool {
+		self.score() == self.max_score() && self.restrictions_met()
+	}
+	// Only allow the free cell at idx to hold the values in allowed.
+	// step, the restarts, anneal and the exact solvers never put any
+	// other value there, though the board may already hold one until
+	// the next restart.
+	pub fn restrict(&mut self, idx: usize, allowed: &[u8]) {
+		assert!(
+			self.is_free(idx),
+			"only a free cell can be restricted"
+		);
+		self.restrictions.insert(idx, allowed.to_vec());
+	}
+	// Whether value may go in the cell at idx
+	pub(crate) fn allows(&self, idx: usize, value: u8) -> bool {
+		self.restrictions
+			.get(&idx)
+			.is_none_or(|allowed| allowed.contains(&value))
+	}
+	// Whether every restricted cell holds a value it allows
+	fn restrictions_met(&self) -> bool {
+		self.restrictions
+			.iter()
+			.all(|(&idx, allowed)| allowed.contains(&self.board[idx]))
+	}
+	// Swap an allowed value into each restricted cell holding one it
+	// doesn't allow, taking the first free cell whose value fits and
+	// which allows the value it is given. A cell nothing fits is left
+	// as it is.
+	pub(crate) fn meet_restrictions(&mut self) {
+		let restricted: Vec<usize> =
+			self.restrictions.keys().copied().collect();
+		for idx in restricted {
+			if self.allows(idx, self.board[idx]) {
+				continue;
+			}
+			if let Some(&other) = self
+				.assignments
+				.iter()
+				.find(|&&other| self.swap_allowed(idx, other))
+			{
+				self.swap(idx, other);
+			}
+		}
+	}
+	// Whether swapping the cells at idx1 and idx2 moves both values
+	// somewhere they are allowed
+	pub(crate) fn swap_allowed(
+		&self,
+		idx1: usize,
+		idx2: usize,
+	) -> bool {
+		self.allows(idx1, self.board[idx2])
+			&& self.allows(idx2, self.board[idx1])
+	}
+	// Require the values a and b to be adjacent, on top of the walk
+	// itself
+	pub fn add_constraint(&mut self, a: u8, b: u8) {
+		let cells = self.board.len();
+		assert!(
+			a != b
+				&& (1..=cells).contains(&(a as usize))
+				&& (1..=cells).contains(&(b as usize)),
+			"a constraint needs two distinct values in [1,n*n]"
+		);
+		self.constraints.push((a, b));
+	}
+	// The number of breaks whose values happen to be adjacent anyway
+	fn links_across_breaks(&self) -> usize {
+		self.breaks
+			.iter()
+			.filter(|&&b| {
+				let (idx1, idx2) =
+					(self.pos[b as usize], self.pos[b as usize + 1]);
+				self.king_distance(idx1, idx2) == 1
+			})
+			.count()
+	}
+	// Whether the walk runs from the value from to the value to
+	// without a break, so each step between them is a king move
+	pub(crate) fn unbroken(&self, from: usize, to: usize) -> bool {
+		!self
+			.breaks
+			.iter()
+			.any(|&b| (from..to).contains(&(b as usize)))
+	}
+	// The number of constraints whose values are adjacent
+	fn satisfied_constraints(&self) -> usize {
+		self.constraints
+			.iter()
+			.filter(|&&(a, b)| {
+				let (idx1, idx2) =
+					(self.pos[a as usize], self.pos[b as usize]);
+				self.king_distance(idx1, idx2) == 1
+			})
+			.count()
+	}
+	// finds the best orbital and returns the new score
+	#[must_use]
+	pub fn step(&mut self, start_score: usize) -> usize {
+		self.step_with_mode(start_score, StepMode::BestImprovement)
+	}
+	// finds an improving orbital, the way mode picks it, and returns
+	// the new score
+	#[must_use]
+	pub fn step_with_mode(
+		&mut self,
+		start_score: usize,
+		mode: StepMode,
+	) -> usize {
+		// update the board with the current best
+		match self.find_swap(start_score, mode) {
+			Some((i, j, high_score)) => {
+				self.swap(i, j);
+				debug_assert!(self.positions_consistent());
+				high_score
+			}
+			None => start_score,
+		}
+	}
+	// finds the swap of two free cells that raises the score the
+	// most above start_score, and the score it reaches, without
+	// making it. A swap that solves the board can't be beaten, so
+	// the search stops at the first one. Ties go to the first in scan
+	// order, the order the free cells are kept in, as only a strictly
+	// better swap replaces the best so far.
+	fn best_swap(
+		&mut self,
+		start_score: usize,
+	) -> Option<(usize, usize, usize)> {
+		self.find_swap(start_score, StepMode::BestImprovement)
+	}
+	// best_swap, but with FirstImprovement the search stops at the
+	// first swap that raises the score at all
+	fn find_swap(
+		&mut self,
+		start_score: usize,
+		mode: StepMode,
+	) -> Option<(usize, usize, usize)> {
+		// a swap reaching this score ends the search
+		let good_enough = match mode {
+			StepMode::BestImprovement => self.max_score(),
+			StepMode::FirstImprovement => start_score + 1,
+		};
+		let mut scan = SwapScan::new(start_score);
+		self.scan_swaps(&mut scan, usize::MAX, good_enough);
+		scan.best
+	}
+	// step, but scoring at most max_swaps candidate swaps per call so
+	// the scan can be spread over many calls. Each call carries on
+	// where the last one left off, unless the board has changed
+	// since, in which case the scan starts over.
+	pub fn step_budget(&mut self, max_swaps: usize) -> StepResult {
+		let (board, mut scan) = match self.scan.take() {
+			Some((board, scan)) if board == self.board => {
+				(board, scan)
+			}
+			_ => (self.board.clone(), SwapScan::new(self.score())),
+		};
+		if !self.scan_swaps(&mut scan, max_swaps, self.max_score()) {
+			self.scan = Some((board, scan));
+			return StepResult::Pending;
+		}
+		match scan.best {
+			Some((i, j, high_score)) => {
+				self.swap(i, j);
+				debug_assert!(self.positions_consistent());
+				StepResult::Done(high_score)
+			}
+			None => StepResult::Done(scan.start_score),
+		}
+	}
+	// Score the swaps of every pair of free cells, in order from
+	// where scan is, remembering the best one that raises the score.
+	// Stops early at a swap reaching good_enough. Returns whether
+	// the scan finished, or false if it ran out of budget swaps
+	// first.
+	fn scan_swaps(
+		&mut self,
+		scan: &mut SwapScan,
+		budget: usize,
+		good_enough: usize,
+	) -> bool {
+		let free = self.assignments.len();
+		let mut left = budget;
+		// for every first index
+		while scan.prev < free {
+			let idx1 = self.assignments[scan.prev];
+			// and every possible other index
+			while scan.next < free {
+				if left == 0 {
+					return false;
+				}
+				let idx2 = self.assignments[scan.next];
+				scan.next += 1;
+				// never move a value where it isn't allowed
+				if !self.swap_allowed(idx1, idx2) {
+					continue;
+				}
+				left -= 1;
+				#[cfg(test)]
+				tests::CANDIDATES.with(|c| c.set(c.get() + 1));
+				// swap the two
+				self.swap(idx1, idx2);
+				// score the new state
+				let score = self.score();
+				// return the board to it's previous state
+				self.swap(idx1, idx2);
+				// save if it's better than before
+				if score > scan.high_score {
+					scan.high_score = score;
+					scan.best = Some((idx1, idx2, score));
+					if score >= good_enough {
+						return true;
+					}
+				};
+			}
+			scan.prev += 1;
+			scan.next = scan.prev + 1;
+		}
+		true
+	}
+	// every swap of two free cells that step would consider, as
+	// (idx1, idx2, change in score), in the order step scans them.
+	// The swaps are tried on a copy, so the board is left alone.
+	pub fn candidate_swaps(
+		&self,
+	) -> impl Iterator<Item = (usize, usize, isize)> {
+		let mut scratch = self.clone();
+		let start_score = self.score() as isize;
+		let free = self.assignments.len();
+		(0..free)
+			.flat_map(move |prev| {
+				(prev + 1..free).map(move |next| (prev, next))
+			})
+			.filter_map(move |(prev, next)| {
+				let idx1 = scratch.assignments[prev];
+				let idx2 = scratch.assignments[next];
+				if !scratch.swap_allowed(idx1, idx2) {
+					return None;
+				}
+				scratch.swap(idx1, idx2);
+				let score = scratch.score() as isize;
+				scratch.swap(idx1, idx2);
+				Some((idx1, idx2, score - start_score))
+			})
+	}
+	// Step until no further progress is made, or max_steps steps
+	// were taken, and return the score that was reached
+	fn climb(
+		&mut self,
+		start_score: usize,
+		max_steps: usize,
+	) -> usize {
+		let mut high_score = start_score;
+		for _ in 0..max_steps {
+			// Calculate the best orbital
+			let round = self.step(high_score);
+			// Stop once progress is no longer being made
+			if round <= high_score {
+				break;
+			}
+			high_score = round;
+		}
+		high_score
+	}
+	// Hillclimb with random restarts until solved and return whether
+	// it solved. On a board with no solution and free cells it never
+	// returns; hillclimb_with_policy takes a bound on the restarts.
+	#[must_use]
+	pub fn hillclimb(&mut self) -> bool {
+		self.hillclimb_with_policy(&RandomRestart, usize::MAX)
+	}
+	// Hillclimb, letting the policy choose every new starting point.
+	// Gives up after max_restarts and returns whether it solved. When
+	// it gives up the board is left at the highest scoring
+	// arrangement seen, so unsolvable puzzles still get a best
+	// effort.
+	pub fn hillclimb_with_policy(
+		&mut self,
+		policy: &dyn RestartPolicy,
+		max_restarts: usize,
+	) -> bool {
+		self.hillclimb_reporting(policy, max_restarts, &mut |_| {})
+	}
+	// hillclimb_with_policy, calling report with the best score each
+	// time it improves
+	fn hillclimb_reporting(
+		&mut self,
+		policy: &dyn RestartPolicy,
+		max_restarts: usize,
+		report: &mut dyn FnMut(usize),
+	) -> bool {
+		let mut high_score = self.score();
+		report(high_score);
+		// with nothing free no restart can change the board
+		if self.assignments.is_empty() {
+			return self.is_solved();
+		}
+		let mut best = (high_score, self.board.clone());
+		let mut restarts = 0;
+		// While a solution hasn't been found
+		while high_score != self.max_score()
+			|| !self.restrictions_met()
+		{
+			if restarts == max_restarts {
+				self.set_board(best.1);
+				return false;
+			}
+			restarts += 1;
+			// RESTART at a new point
+			high_score = policy.restart(self);
+			let max_steps =
+				policy.step_limit(restarts).unwrap_or(usize::MAX);
+			high_score = self.climb(high_score, max_steps);
+			if high_score > best.0 {
+				best = (high_score, self.board.clone());
+				report(high_score);
+			}
+		}
+		true
+	}
+	// Hillclimb from the board as it is, then with up to max_restarts
+	// random restarts, recording (iteration, score) at the start,
+	// after every step and every restart, with the iteration
+	// counting all of them. When it gives up the board is left at
+	// the highest scoring arrangement seen, which is recorded last.
+	// Returns whether it solved along with the trace.
+	pub fn hillclimb_trace(
+		&mut self,
+		max_restarts: usize,
+	) -> (bool, Vec<(usize, usize)>) {
+		let mut score = self.score();
+		let mut best = (score, self.board.clone());
+		let mut iteration = 0;
+		let mut trace = vec![(iteration, score)];
+		for restart in 0..=max_restarts {
+			if restart > 0 {
+				score = self.random_start();
+				iteration += 1;
+				trace.push((iteration, score));
+			}
+			loop {
+				let next = self.step(score);
+				if next <= score {
+					break;
+				}
+				score = next;
+				iteration += 1;
+				trace.push((iteration, score));
+			}
+			if score == self.max_score() && self.restrictions_met() {
+				return (true, trace);
+			}
+			if score > best.0 {
+				best = (score, self.board.clone());
+			}
+		}
+		self.set_board(best.1);
+		trace.push((iteration + 1, self.score()));
+		(false, trace)
+	}
+	// Hillclimb like hillclimb_trace, recording the swaps made.
+	// Applied in order to the board as it was, they reproduce the
+	// board left behind. For a random restart the list opens with
+	// the swaps taking the board as it was to that restart's
+	// starting point.
+	pub fn solve_record(
+		&mut self,
+		max_restarts: usize,
+	) -> (bool, Vec<(usize, usize)>) {
+		let initial = self.board.clone();
+		let mut best = (0, Vec::new(), Vec::new());
+		for restart in 0..=max_restarts {
+			let mut score = self.score();
+			let mut moves = Vec::new();
+			if restart > 0 {
+				score = self.random_start();
+				moves = self.swaps_from(&initial);
+			}
+			while let Some((idx1, idx2, next)) = self.best_swap(score)
+			{
+				self.swap(idx1, idx2);
+				moves.push((idx1, idx2));
+				score = next;
+			}
+			if score == self.max_score() && self.restrictions_met() {
+				return (true, moves);
+			}
+			if restart == 0 || score > best.0 {
+				best = (score, self.board.clone(), moves);
+			}
+		}
+		self.set_board(best.1);
+		(false, best.2)
+	}
+	// swaps that, applied in order, turn board into this board
+	fn swaps_from(&self, board: &[u8]) -> Vec<(usize, usize)> {
+		let mut board = board.to_vec();
+		let mut pos = vec![0; board.len() + 1];
+		for (idx, &v) in board.iter().enumerate() {
+			pos[v as usize] = idx;
+		}
+		let mut swaps = Vec::new();
+		for idx in 0..board.len() {
+			let want = self.board[idx];
+			if board[idx] != want {
+				let other = pos[want as usize];
+				board.swap(idx, other);
+				pos[board[other] as usize] = other;
+				pos[want as usize] = idx;
+				swaps.push((idx, other));
+			}
+		}
+		swaps
+	}
+	// Hillclimb with one random restart per seed, stopping at the
+	// first that solves. Each restart shuffles the puzzle as
+	// to_puzzle fills it, after reseeding the generator with its
+	// seed, so a seed gives the same restart whatever the board held
+	// before and whichever seeds came first. The generator is put
+	// back as it was afterwards. When none solves the board is left
+	// at the highest scoring arrangement seen.
+	pub fn hillclimb_seeds(&mut self, seeds: &[u64]) -> bool {
+		let saved = RNG.with(|rng_cell| rng_cell.borrow().clone());
+		let filled = self.to_puzzle().into_board();
+		let mut best = (self.score(), self.board.clone());
+		for &seed in seeds {
+			if best.0 == self.max_score() {
+				break;
+			}
+			self.set_board(filled.clone());
+			seed_rng(seed);
+			let start_score = self.random_start();
+			let high_score = self.climb(start_score, usize::MAX);
+			if high_score > best.0 {
+				best = (high_score, self.board.clone());
+			}
+		}
+		RNG.with(|rng_cell| *rng_cell.borrow_mut() = saved);
+		self.set_board(best.1);
+		self.is_solved()
+	}
+	// Hillclimb with random restarts on another thread. The best
+	// completion_fraction so far is sent over the channel each time
+	// it improves, and the channel closes once the solve is over.
+	// The handle gives back whether it solved along with the final
+	// state.
+	pub fn solve_with_progress(
+		&self,
+		max_restarts: usize,
+	) -> (Receiver<f64>, JoinHandle<(bool, State)>) {
+		let (sender, receiver) = mpsc::channel();
+		let mut state = self.clone();
+		let handle = thread::spawn(move || {
+			let max_score = state.max_score();
+			let solved = state.hillclimb_reporting(
+				&RandomRestart,
+				max_restarts,
+				&mut |score| {
+					// nobody listening is not a reason to stop
+					let _ = sender.send(fraction(score, max_score));
+				},
+			);
+			(solved, state)
+		});
+		(receiver, handle)
+	}
+	// How close the board is to a solution, from 0.0 to 1.0
+	pub fn completion_fraction(&self) -> f64 {
+		fraction(self.score(), self.max_score())
+	}
+	// Hillclimb with random restarts like hillclimb_with_policy, and
+	// also return a log of every restart and swap in the order they
+	// were made
+	pub fn solve_explained(
+		&mut self,
+		max_restarts: usize,
+	) -> (bool, Vec<String>) {
+		let n = self.n;
+		let cell =
+			move |idx: usize| format!("({},{})", idx / n, idx % n);
+		let mut log = Vec::new();
+		let mut high_score = self.score();
+		let mut best = (high_score, self.board.clone());
+		let mut restarts = 0;
+		while high_score != self.max_score()
+			|| !self.restrictions_met()
+		{
+			if restarts == max_restarts {
+				log.push(format!(
+					"Gave up after {} restarts: best score {}",
+					restarts, best.0
+				));
+				self.set_board(best.1);
+				return (false, log);
+			}
+			restarts += 1;
+			high_score = self.random_start();
+			log.push(format!(
+				"Restart #{}: score {}",
+				restarts, high_score
+			));
+			while let Some((i, j, score)) = self.best_swap(high_score)
+			{
+				self.swap(i, j);
+				log.push(format!(
+					"Swapped cells {}\u{2194}{}: score {}\u{2192}{}",
+					cell(i),
+					cell(j),
+					high_score,
+					score
+				));
+				high_score = score;
+			}
+			if high_score > best.0 {
+				best = (high_score, self.board.clone());
+			}
+		}
+		log.push("Solved!".to_string());
+		(true, log)
+	}
+	// The (row, col) of the two free cells whose swap raises the
+	// score the most, or None when no swap raises it
+	pub fn hint(
+		&mut self,
+	) -> Option<((usize, usize), (usize, usize))> {
+		let n = self.n;
+		let cell = move |idx: usize| (idx / n, idx % n);
+		self.best_swap(self.score())
+			.map(|(i, j, _)| (cell(i), cell(j)))
+	}
+	// Hillclimb with random restarts and return the fewest defects,
+	// links missing from a full walk, that were reached. The board is
+	// left at that arrangement.
+	pub fn min_defects(&mut self, max_restarts: usize) -> usize {
+		self.hillclimb_with_policy(&RandomRestart, max_restarts);
+		self.max_score() - self.score()
+	}
+	// Solve with the strategy that suits the size of the board and
+	// return whether it solved:
+	// - n <= 4: solve_exact, which also proves when there is no
+	//   solution
+	// - n <= 6: hillclimb with random restarts, giving up after
+	//   10_000 restarts
+	// - larger: anneal, giving up after 100 runs of 20_000 swaps
+	pub fn auto_solve(&mut self) -> bool {
+		match self.n {
+			0..=4 => self.solve_exact(),
+			5..=6 => {
+				self.hillclimb_with_policy(&RandomRestart, 10_000)
+			}
+			_ => (0..100).any(|_| self.anneal(20_000)),
+		}
+	}
+	// Make the cell at idx a clue holding value, or free it with
+	// None, then solve again from the board as it stands: climb from
+	// there first, and only then fall back to 10_000 random restarts.
+	// Returns whether it solved. Returns false and changes nothing
+	// when idx is off the board, value is not in [1,n*n], or value is
+	// already a clue in another cell.
+	pub fn update_clue(
+		&mut self,
+		idx: usize,
+		value: Option<u8>,
+	) -> bool {
+		if idx >= self.board.len() {
+			return false;
+		}
+		match value {
+			Some(v) => {
+				if v == 0 || v as usize > self.board.len() {
+					return false;
+				}
+				let from = self.pos[v as usize];
+				if from != idx && !self.is_free(from) {
+					return false;
+				}
+				// whatever idx held moves to the free cell v came
+				// from
+				self.swap(idx, from);
+				self.assignments.retain(|&cell| cell != idx);
+			}
+			None => {
+				if !self.is_free(idx) {
+					self.assignments.push(idx);
+				}
+			}
+		}
+		// this also drops a paused scan, which was over the old free
+		// cells
+		self.apply_order();
+		let score = self.climb(self.score(), usize::MAX);
+		(score == self.max_score() && self.restrictions_met())
+			|| self.hillclimb_with_policy(&RandomRestart, 10_000)
+	}
+	// Whether the puzzle has a solution. For n <= 5 this is exact,
+	// settled by solve_exact. Larger boards are first checked for
+	// clues that are too far apart to ever be joined, and then given
+	// 1_000 hillclimbing restarts, so a false there only means no
+	// solution was found.
+	pub fn is_solvable(&self) -> bool {
+		if self.n <= 5 {
+			return self.clone().solve_exact();
+		}
+		self.clues_reachable()
+			&& self
+				.clone()
+				.hillclimb_with_policy(&RandomRestart, 1_000)
+	}
+	// Free the fixed cells one at a time, in an order shuffled from
+	// seed, keeping each one free as long as is_solvable still holds.
+	// Only solvability is kept, not uniqueness, so the puzzle may end
+	// up with more solutions than it started with.
+	pub fn minimize_clues(&mut self, seed: u64) {
+		seed_rng(seed);
+		let mut clues: Vec<usize> = (0..self.board.len())
+			.filter(|idx| !self.assignments.contains(idx))
+			.collect();
+		RNG.with(|rng_cell| {
+			clues.shuffle(&mut *rng_cell.borrow_mut())
+		});
+		for idx in clues {
+			self.assignments.push(idx);
+			self.apply_order();
+			if !self.is_solvable() {
+				self.assignments.retain(|&cell| cell != idx);
+				self.apply_order();
+			}
+		}
+	}
+	// A bound on the score any filling of the free cells can reach:
+	// max_score less every link that can't be made
+	pub fn upper_bound(&self) -> usize {
+		self.max_score() - self.impossible_links().len()
+	}
+	// The fixed cells which keep the score below max_score, as the
+	// value they hold can't be linked to the value before or after it
+	pub fn conflicting_clues(&self) -> Vec<usize> {
+		let mut clues: Vec<usize> = self
+			.impossible_links()
+			.into_iter()
+			.flat_map(|v| vec![self.pos[v], self.pos[v + 1]])
+			.filter(|idx| !self.is_free(*idx))
+			.collect();
+		clues.sort_unstable();
+		clues.dedup();
+		clues
+	}
+	// Every v whose link to v+1 no filling of the free cells can
+	// make: either both are fixed and not adjacent, or one is fixed
+	// with no free cell beside it that may hold the other
+	fn impossible_links(&self) -> Vec<usize> {
+		let fixed = |idx: usize| !self.is_free(idx);
+		// whether a free cell beside idx may hold value
+		let free_beside = |idx: usize, value: usize| {
+			self.king_neighbors(idx)
+				.into_iter()
+				.any(|x| !fixed(x) && self.allows(x, value as u8))
+		};
+		(1..self.board.len())
+			.filter(|&v| self.unbroken(v, v + 1))
+			.filter(|&v| {
+				let (idx1, idx2) = (self.pos[v], self.pos[v + 1]);
+				match (fixed(idx1), fixed(idx2)) {
+					(true, true) => {
+						self.king_distance(idx1, idx2) != 1
+					}
+					(true, false) => !free_beside(idx1, v + 1),
+					(false, true) => !free_beside(idx2, v),
+					(false, false) => false,
+				}
+			})
+			.collect()
+	}
+	// Whether every pair of consecutive fixed values is close enough
+	// for the walk to get from one to the other
+	fn clues_reachable(&self) -> bool {
+		let mut clues: Vec<(u8, usize)> = (0..self.board.len())
+			.filter(|idx| !self.assignments.contains(idx))
+			.map(|idx| (self.board[idx], idx))
+			.collect();
+		clues.sort_unstable();
+		clues.windows(2).all(|pair| {
+			let ((v1, idx1), (v2, idx2)) = (pair[0], pair[1]);
+			!self.unbroken(v1 as usize, v2 as usize)
+				|| self.king_distance(idx1, idx2)
+					<= (v2 - v1) as usize
+		})
+	}
+	// How many of v's neighbors hold v-1 or v+1, so 0, 1 or 2.
+	// Without constraints or breaks the links of all the values add
+	// up to twice the score, since score counts each link once.
+	pub fn value_links(&self, v: u8) -> usize {
+		match self.pos.get(v as usize) {
+			Some(&idx) if v != 0 => self.links_at(idx),
+			_ => 0,
+		}
+	}
+	// The links of the walk as it stands: the ((row, col), (row,
+	// col)) of k and k+1 for each pair a king move apart, and the
+	// (k, k+1) pairs that aren't. Pairs split by a break are in
+	// neither, and constraints aren't included, so without
+	// constraints there are score segments.
+	pub fn link_status(&self) -> (Vec<Segment>, Vec<(u8, u8)>) {
+		let coords = |idx: usize| (idx / self.n, idx % self.n);
+		let mut segments = Vec::new();
+		let mut missing = Vec::new();
+		for v in
+			(1..self.board.len()).filter(|&v| self.unbroken(v, v + 1))
+		{
+			let (from, to) = (self.pos[v], self.pos[v + 1]);
+			if self.king_distance(from, to) == 1 {
+				segments.push((coords(from), coords(to)));
+			} else {
+				missing.push((v as u8, v as u8 + 1));
+			}
+		}
+		(segments, missing)
+	}
+	// The board with an arrow after each value pointing to the cell
+	// holding the next value, or a · when that cell isn't adjacent.
+	// The last value has nothing after it.
+	pub fn render_arrows(&self) -> String {
+		let width = self.board.len().to_string().len();
+		let mut out = String::new();
+		for values in self.board.chunks(self.n) {
+			let cells: Vec<String> = values
+				.iter()
+				.map(|&v| {
+					let arrow = match self.pos.get(v as usize + 1) {
+						Some(&next) => {
+							self.arrow(self.pos[v as usize], next)
+						}
+						None => ' ',
+					};
+					format!("{:>width$}{}", v, arrow, width = width)
+				})
+				.collect();
+			out.push_str(cells.join(" ").trim_end());
+			out.push('\n');
+		}
+		out
+	}
+	// The arrow pointing from the cell at idx1 to the cell at idx2,
+	// or a · when they aren't adjacent
+	fn arrow(&self, idx1: usize, idx2: usize) -> char {
+		if self.king_distance(idx1, idx2) != 1 {
+			return '·';
+		}
+		let (row1, col1) = (idx1 / self.n, idx1 % self.n);
+		let (row2, col2) = (idx2 / self.n, idx2 % self.n);
+		let dr = row2 as isize - row1 as isize;
+		let dc = col2 as isize - col1 as isize;
+		match (dr, dc) {
+			(-1, -1) => '↖',
+			(-1, 0) => '↑',
+			(-1, 1) => '↗',
+			(0, -1) => '←',
+			(0, 1) => '→',
+			(1, -1) => '↙',
+			(1, 0) => '↓',
+			(1, 1) => '↘',
+			_ => '·',
+		}
+	}
+	// The pairs of cells i < j with board[i] > board[j], how far the
+	// board read in row order is from sorted
+	pub fn inversions(&self) -> usize {
+		self.board
+			.iter()
+			.enumerate()
+			.map(|(i, &a)| {
+				self.board[i + 1..].iter().filter(|&&b| a > b).count()
+			})
+			.sum()
+	}
+	// links_at for every cell, by row. Without constraints or breaks
+	// the entries add up to twice the score.
+	pub fn score_matrix(&self) -> Vec<Vec<u8>> {
+		(0..self.n)
+			.map(|row| {
+				(0..self.n)
+					.map(|col| {
+						self.links_at(row * self.n + col) as u8
+					})
+					.collect()
+			})
+			.collect()
+	}
+	// The links from the cell at idx to any of its 8 neighbors which
+	// hold the next or previous value
+	fn links_at(&self, idx: usize) -> usize {
+		let goal = self.board[idx];
+		self.king_neighbors(idx)
+			.into_iter()
+			.filter(|&x| {
+				let v = self.board[x];
+				v == goal + 1 || v + 1 == goal
+			})
+			.count()
+	}
+	// For every cell, the cells a king could move to from it. The
+	// walk is a path through this graph that visits every cell.
+	pub fn adjacency_list(&self) -> Vec<Vec<usize>> {
+		(0..self.board.len())
+			.map(|idx| self.king_neighbors(idx))
+			.collect()
+	}
+	// All (up to 8) positions a king could move to from idx, or on
+	// another topology the cells next to it there
+	fn king_neighbors(&self, idx: usize) -> Vec<usize> {
+		self.topology.neighbors(idx, self.n)
+	}
+	// The cells a king could move to from the cell holding v, as a
+	// bitset with cell idx at bit idx % 64 of word idx / 64, so the
+	// sets can be intersected a word at a time. Empty when v isn't on
+	// the board.
+	fn value_neighbor_mask(&self, v: u8) -> Vec<u64> {
+		let mut mask = vec![0; self.board.len().div_ceil(64)];
+		if let Some(&idx) =
+			self.pos.get(v as usize).filter(|_| v != 0)
+		{
+			for neighbor in self.king_neighbors(idx) {
+				mask[neighbor / 64] |= 1 << (neighbor % 64);
+			}
+		}
+		mask
+	}
+	// The fewest king moves from idx1 to idx2, or on another
+	// topology the fewest steps between neighbors
+	fn king_distance(&self, idx1: usize, idx2: usize) -> usize {
+		self.topology.distance(idx1, idx2, self.n)
+	}
+	// Swap the values in two cells, keeping pos in step
+	fn swap(&mut self, idx1: usize, idx2: usize) {
+		self.board.swap(idx1, idx2);
+		self.pos[self.board[idx1] as usize] = idx1;
+		self.pos[self.board[idx2] as usize] = idx2;
+	}
+	// The board, taken out of the state. Moving the field out
+	// directly won't do, as State may implement Drop.
+	pub(crate) fn into_board(mut self) -> Vec<u8> {
+		std::mem::take(&mut self.board)
+	}
+	// Replace the whole board, such as with a copy saved earlier
+	fn set_board(&mut self, mut board: Vec<u8>) {
+		board.shrink_to_fit();
+		self.board = board;
+		self.index_positions();
+	}
+	// Whether pos is still the inverse of the board
+	fn positions_consistent(&self) -> bool {
+		(1..=self.board.len())
+			.all(|v| self.board.get(self.pos[v]) == Some(&(v as u8)))
+	}
+	// Whether the board holds each of [1,n*n] exactly once
+	fn board_is_permutation(&self) -> bool {
+		let mut seen = vec![false; self.board.len() + 1];
+		self.board.iter().all(|&value| {
+			let value = value as usize;
+			value != 0
+				&& value < seen.len()
+				&& !std::mem::replace(&mut seen[value], true)
+		})
+	}
+	// Whether the cell at idx is free
+	fn is_free(&self, idx: usize) -> bool {
+		self.assignments.contains(&idx)
+	}
+	// Rebuild pos from the board
+	fn index_positions(&mut self) {
+		self.pos = vec![0; self.board.len() + 1];
+		for (idx, &value) in self.board.iter().enumerate() {
+			self.pos[value as usize] = idx;
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use proptest::prelude::{
+		any, prop, prop_assert, prop_assert_eq, proptest, Just,
+		Strategy,
+	};
+	use std::cell::Cell;
+	use std::collections::HashSet;
+
+	thread_local! {
+		// How many swaps best_swap has scored on this thread
+		pub(super) static CANDIDATES: Cell<usize> = const { Cell::new(0) };
+		// How many states Drop has warned about on this thread
+		pub(super) static INVALID_DROPS: Cell<usize> = const { Cell::new(0) };
+	}
+
+	// The #[bench] benchmarks, only built with the nightly feature.
+	// benches/solve.rs has criterion versions that run on stable.
+	#[cfg(feature = "nightly")]
+	mod benches {
+		use super::*;
+
+		extern crate test;
+		use test::Bencher;
+
+		// A large, solved board
+		fn solved_n_eq_8() -> Vec<u8> {
+			#[rustfmt::skip]
+			#[allow(clippy::zero_prefixed_literal)]
+			let solved = vec![
+				08, 07, 06, 05, 04, 03, 02, 01,
+				09, 10, 11, 12, 13, 14, 15, 16,
+				24, 23, 22, 21, 20, 19, 18, 17,
+				25, 26, 27, 28, 29, 30, 31, 32,
+				40, 39, 38, 37, 36, 35, 34, 33,
+				41, 42, 43, 44, 45, 46, 47, 48,
+				56, 55, 54, 53, 52, 51, 50, 49,
+				57, 58, 59, 60, 61, 62, 63, 64,
+			];
+			solved
+		}
+		#[bench]
+		fn hillclimb_n_eq_8(b: &mut Bencher) {
+			let solved = solved_n_eq_8();
+			b.iter(|| {
+				// randomly place zeros
+				let (corruption_amount, seed) =
+					RNG.with(|rng_cell| {
+						let mut rng = rng_cell.borrow_mut();
+						let min_corruption = 8;
+						let max_corruption = 8 * 2;
+						let corruption_amount = (rng.gen::<usize>()
+							% (max_corruption - min_corruption))
+							+ min_corruption;
+						(corruption_amount, rng.gen())
+					});
+				// Make a new state
+				let mut state = corrupt(
+					solved.clone(),
+					8,
+					corruption_amount,
+					seed,
+				);
+				// climb, asserting that the max score was reached
+				assert!(state.hillclimb());
+			});
+		}
+		#[bench]
+		fn score_n_eq_8(b: &mut Bencher) {
+			let state = State::new(solved_n_eq_8(), 8).unwrap();
+			b.iter(|| test::black_box(&state).score());
+		}
+		// Climb a seeded random start of the n=8 board with 16 blanks
+		// to its local maximum, taking steps the way mode picks
+		// them
+		fn climb_n_eq_8(b: &mut Bencher, mode: StepMode) {
+			let mut working_board = solved_n_eq_8();
+			for idx in (0..working_board.len()).step_by(4) {
+				working_board[idx] = 0;
+			}
+			let state = State::new(working_board, 8).unwrap();
+			seed_rng(8);
+			b.iter(|| {
+				let mut state = state.clone();
+				let mut score = state.random_start();
+				loop {
+					let next = state.step_with_mode(score, mode);
+					if next == score {
+						break;
+					}
+					score = next;
+				}
+			});
+		}
+		#[bench]
+		fn climb_n_eq_8_best_improvement(b: &mut Bencher) {
+			climb_n_eq_8(b, StepMode::BestImprovement);
+		}
+		#[bench]
+		fn climb_n_eq_8_first_improvement(b: &mut Bencher) {
+			climb_n_eq_8(b, StepMode::FirstImprovement);
+		}
+		// Hillclimb the n=8 board with exactly percent of its cells
+		// blanked. The seed keeps runs comparable. Dense boards are
+		// rarely solved by hillclimbing, so every run gets the same
+		// budget of restarts and only its cost is measured.
+		fn hillclimb_n8_density(
+			b: &mut Bencher,
+			percent: usize,
+			seed: u64,
+		) {
+			let solved = solved_n_eq_8();
+			let corruption_amount = solved.len() * percent / 100;
+			seed_rng(seed);
+			b.iter(|| {
+				let seed =
+					RNG.with(|rng_cell| rng_cell.borrow_mut().gen());
+				let mut state = corrupt(
+					solved.clone(),
+					8,
+					corruption_amount,
+					seed,
+				);
+				state.hillclimb_with_policy(&RandomRestart, 10);
+			});
+		}
+		#[bench]
+		fn hillclimb_n8_density_25(b: &mut Bencher) {
+			hillclimb_n8_density(b, 25, 25);
+		}
+		#[bench]
+		fn hillclimb_n8_density_50(b: &mut Bencher) {
+			hillclimb_n8_density(b, 50, 50);
+		}
+		#[bench]
+		fn hillclimb_n8_density_75(b: &mut Bencher) {
+			hillclimb_n8_density(b, 75, 75);
+		}
+	}
+	// Generate a solvable puzzle of side n with density of its cells
+	// blank, from seed, and assert hillclimbing solves it within a
+	// bounded number of restarts
+	fn assert_solvable(n: usize, density: f64, seed: u64) {
+		seed_rng(seed);
+		let blanks = (density * (n * n) as f64).round() as usize;
+		let mut state = random_puzzle(n, blanks).unwrap();
+		assert!(
+			state.hillclimb_with_policy(&RandomRestart, 1_000),
+			"n={} density={} seed={}\n{}",
+			n,
+			density,
+			seed,
+			state.to_puzzle_string()
+		);
+		assert_eq!(state.score(), state.max_score());
+	}
+	#[test]
+	fn hillclimb_should_return_at_once_without_free_cells() {
+		let mut solved =
+			State::new(vec![4, 5, 1, 3, 2, 6, 9, 8, 7], 3).unwrap();
+		assert!(solved.hillclimb());
+		let mut unsolved =
+			State::new(vec![1, 2, 3, 4, 5, 6, 7, 8, 9], 3).unwrap();
+		assert!(!unsolved.hillclimb());
+		assert!(!unsolved.hillclimb_with_policy(&GreedyRestart, 10));
+		assert_eq!(unsolved.board, vec![1, 2, 3, 4, 5, 6, 7, 8, 9]);
+	}
+	#[test]
+	fn hillclimb_trace_should_count_every_step() {
+		for board in [
+			vec![0, 0, 1, 0, 2, 0, 9, 0, 0],
+			// 1 and 2 are fixed in opposite corners
+			vec![1, 0, 0, 0, 0, 0, 0, 0, 2],
+		] {
+			let mut state = State::new(board, 3).unwrap();
+			let (solved, trace) = state.hillclimb_trace(50);
+			assert!(!trace.is_empty());
+			assert!(trace
+				.windows(2)
+				.all(|pair| pair[0].0 < pair[1].0));
+			assert_eq!(trace.last().unwrap().1, state.score());
+			assert_eq!(solved, state.is_solved());
+		}
+	}
+	#[test]
+	fn solve_record_should_replay_to_the_solution() {
+		seed_rng(3);
+		for board in [
+			vec![0, 0, 1, 0, 2, 0, 9, 0, 0],
+			vec![0, 0, 0, 0, 0, 3, 4, 0, 0, 0, 0, 0, 12, 0, 0, 0],
+		] {
+			let n = if board.len() == 9 { 3 } else { 4 };
+			let mut state = State::new(board.clone(), n).unwrap();
+			state.random_start();
+			let mut replay = state.board.clone();
+			let (solved, moves) = state.solve_record(1_000);
+			assert!(solved);
+			for &(idx1, idx2) in &moves {
+				replay.swap(idx1, idx2);
+			}
+			assert_eq!(replay, state.board);
+			let replayed = State::new(replay, n).unwrap();
+			assert_eq!(replayed.score(), replayed.max_score());
+		}
+		// with no solution the moves lead to the best board kept
+		let mut state =
+			State::new(vec![1, 0, 0, 0, 0, 0, 0, 0, 2], 3).unwrap();
+		state.random_start();
+		let mut replay = state.board.clone();
+		let (solved, moves) = state.solve_record(20);
+		assert!(!solved);
+		for &(idx1, idx2) in &moves {
+			replay.swap(idx1, idx2);
+		}
+		assert_eq!(replay, state.board);
+	}
+	#[test]
+	fn clue_bounding_box_should_be_tight() {
+		assert_eq!(
+			State::new_empty(4).unwrap().clue_bounding_box(),
+			None
+		);
+		// clues at (1,2), (2,1) and (2,3)
+		let state = State::new(
+			vec![0, 0, 0, 0, 0, 0, 7, 0, 0, 5, 0, 9, 0, 0, 0, 0],
+			4,
+		)
+		.unwrap();
+		assert_eq!(state.clue_bounding_box(), Some(((1, 1), (2, 3))));
+		let state =
+			State::new(vec![0, 0, 0, 0, 4, 0, 0, 0, 0], 3).unwrap();
+		assert_eq!(state.clue_bounding_box(), Some(((1, 1), (1, 1))));
+	}
+	#[test]
+	fn candidate_swaps_should_match_step() {
+		seed_rng(7);
+		for _ in 0..20 {
+			let mut state = State::new_empty(4).unwrap();
+			state.random_start();
+			let board = state.board.clone();
+			let candidates: Vec<_> =
+				state.candidate_swaps().collect();
+			assert_eq!(state.board, board);
+			// 16 free cells pair up 120 ways
+			assert_eq!(candidates.len(), 120);
+			// the first of the largest changes, as step breaks ties
+			let best = candidates
+				.iter()
+				.min_by_key(|&&(_, _, delta)| {
+					std::cmp::Reverse(delta)
+				})
+				.unwrap();
+			let start_score = state.score();
+			let score = state.step(start_score);
+			if best.2 > 0 {
+				assert_eq!(
+					score as isize,
+					start_score as isize + best.2
+				);
+				let mut expected = board.clone();
+				expected.swap(best.0, best.1);
+				assert_eq!(state.board, expected);
+			} else {
+				assert_eq!(state.board, board);
+			}
+		}
+	}
+	#[cfg(all(feature = "debug-invariants", debug_assertions))]
+	#[test]
+	fn drop_should_only_warn_about_corrupt_boards() {
+		INVALID_DROPS.with(|c| c.set(0));
+		let mut state =
+			State::new(vec![0, 0, 1, 0, 2, 0, 9, 0, 0], 3).unwrap();
+		assert!(state.hillclimb_trace(10).0);
+		drop(state.clone());
+		assert_eq!(INVALID_DROPS.with(Cell::get), 0);
+		state.board[0] = state.board[1];
+		drop(state);
+		assert_eq!(INVALID_DROPS.with(Cell::get), 1);
+	}
+	#[test]
+	fn value_neighbor_mask_should_mark_the_king_moves() {
+		let state = State::new((1..=16).collect(), 4).unwrap();
+		let count = |mask: Vec<u64>| {
+			mask.iter().map(|w| w.count_ones()).sum::<u32>()
+		};
+		// 1 sits in the top left corner, next to cells 1, 4 and 5
+		assert_eq!(state.value_neighbor_mask(1), vec![0b11_0010]);
+		assert_eq!(count(state.value_neighbor_mask(1)), 3);
+		assert_eq!(count(state.value_neighbor_mask(2)), 5);
+		assert_eq!(count(state.value_neighbor_mask(6)), 8);
+		assert_eq!(count(state.value_neighbor_mask(0)), 0);
+		assert_eq!(count(state.value_neighbor_mask(17)), 0);
+		// boards past 64 cells take more words, and the last cell
+		// (80) is next to 70, 71 and 79
+		let state = State::new_empty(9).unwrap();
+		let v = state.board[80];
+		assert_eq!(
+			state.value_neighbor_mask(v),
+			vec![0, 1 << 6 | 1 << 7 | 1 << 15]
+		);
+	}
+	#[test]
+	fn link_status_should_split_the_links() {
+		let mut state =
+			State::new(vec![0, 0, 1, 0, 2, 0, 9, 0, 0], 3).unwrap();
+		// 3 4 1
+		// 5 2 6
+		// 9 7 8
+		let (segments, missing) = state.link_status();
+		assert_eq!(segments.len(), state.score());
+		assert_eq!(
+			segments,
+			vec![
+				((0, 2), (1, 1)),
+				((1, 1), (0, 0)),
+				((0, 0), (0, 1)),
+				((0, 1), (1, 0)),
+				((1, 2), (2, 1)),
+				((2, 1), (2, 2))
+			]
+		);
+		assert_eq!(missing, vec![(5, 6), (8, 9)]);
+		assert!(state.hillclimb());
+		let (segments, missing) = state.link_status();
+		assert_eq!(segments.len(), state.score());
+		assert!(missing.is_empty());
+		// nothing is missing across a break
+		let state = State::with_segments(
+			vec![1, 2, 3, 6, 5, 4, 7, 8, 9],
+			3,
+			&[3],
+		)
+		.unwrap();
+		assert_eq!(state.link_status().0.len(), 7);
+		assert!(state.link_status().1.is_empty());
+	}
+	#[test]
+	fn from_options_should_round_trip() {
+		let cells = vec![
+			None,
+			None,
+			Some(1),
+			None,
+			Some(2),
+			None,
+			Some(9),
+			None,
+			None,
+		];
+		let mut state =
+			State::from_options(cells.clone(), 3).unwrap();
+		assert_eq!(state.to_puzzle_string(), ". . 1\n. 2 .\n9 . .\n");
+		assert_eq!(Vec::<Option<u8>>::from(&state), cells);
+		// solving only changes the free cells
+		assert!(state.hillclimb());
+		assert_eq!(Vec::<Option<u8>>::from(&state), cells);
+		assert_eq!(
+			State::from_options(vec![Some(0); 4], 2).unwrap_err(),
+			KingsWalkError::ValueOutOfRange(0)
+		);
+		assert_eq!(
+			State::from_options(vec![None; 3], 2).unwrap_err(),
+			KingsWalkError::BoardLength
+		);
+	}
+	#[test]
+	fn board_access_should_borrow() {
+		let state = State::new_empty(3).unwrap();
+		assert!(std::ptr::eq(state.board(), state.board.as_slice()));
+		assert!(std::ptr::eq(state.board(), state.as_ref()));
+		match state.free_values() {
+			Cow::Borrowed(values) => {
+				assert!(std::ptr::eq(values, state.board()))
+			}
+			Cow::Owned(_) => panic!("copied the board"),
+		}
+		let mut state =
+			State::new(vec![0, 0, 1, 0, 2, 0, 9, 0, 0], 3).unwrap();
+		state.set_board(vec![4, 5, 1, 3, 2, 6, 9, 8, 7]);
+		assert_eq!(*state.free_values(), [4, 5, 3, 6, 8, 7]);
+		state.reorder_assignments(AssignmentOrder::Spiral);
+		assert_eq!(*state.free_values(), [4, 5, 3, 6, 8, 7]);
+	}
+	#[test]
+	fn memory_footprint_should_scale_with_cells() {
+		let small = State::new_empty(6).unwrap().memory_footprint();
+		let large = State::new_empty(12).unwrap().memory_footprint();
+		// four times the cells, give or take the fixed struct size
+		assert!(large > 3 * small && large < 5 * small);
+		// climbing and restarting shouldn't grow it
+		let mut state = State::new_empty(6).unwrap();
+		state.hillclimb_trace(10);
+		assert_eq!(state.memory_footprint(), small);
+	}
+	#[test]
+	fn hillclimb_seeds_should_be_reproducible() {
+		let puzzle = State::new(
+			vec![0, 0, 0, 0, 0, 3, 4, 0, 0, 0, 0, 0, 12, 0, 0, 0],
+			4,
+		)
+		.unwrap();
+		let seeds: Vec<u64> = (0..20).collect();
+		let mut first = puzzle.clone();
+		let solved = first.hillclimb_seeds(&seeds);
+		assert!(solved);
+		for _ in 0..3 {
+			let mut again = puzzle.clone();
+			// a different board going in doesn't change the outcome
+			again.random_start();
+			assert_eq!(again.hillclimb_seeds(&seeds), solved);
+			assert_eq!(again.board, first.board);
+		}
+		// no seeds leaves the board alone
+		let mut state = puzzle.clone();
+		assert!(!state.hillclimb_seeds(&[]));
+		assert_eq!(state.board, puzzle.board);
+		// and the generator carries on as if it wasn't called
+		let draw = || {
+			RNG.with(|rng_cell| rng_cell.borrow_mut().gen::<u64>())
+		};
+		seed_rng(11);
+		let expected = draw();
+		seed_rng(11);
+		puzzle.clone().hillclimb_seeds(&seeds);
+		assert_eq!(draw(), expected);
+	}
+	#[test]
+	fn hillclimb_should_solve_every_size() {
+		for n in 3..=8 {
+			for &density in &[0.1, 0.25] {
+				assert_solvable(n, density, n as u64);
+			}
+		}
+		// denser boards only while they stay quick to climb
+		for n in 3..=6 {
+			assert_solvable(n, 0.5, n as u64);
+		}
+	}
+	#[test]
+	fn hillclimb_should_solve_n_eq_4_high_density() {
+		// Make a new state
+		let mut state = State::new(
+			vec![9, 8, 7, 6, 0, 3, 4, 0, 0, 0, 0, 0, 12, 0, 0, 0],
+			4,
+		)
+		.unwrap();
+		// climb, asserting that the max score was reached
+		assert!(state.hillclimb());
+	}
+	#[test]
+	fn hillclimb_should_solve_n_eq_4_low_density() {
+		// Make a new state
+		let mut state = State::new(
+			vec![0, 0, 0, 0, 0, 3, 4, 0, 0, 0, 0, 0, 12, 0, 0, 0],
+			4,
+		)
+		.unwrap();
+		// climb, asserting that the max score was reached
+		assert!(state.hillclimb());
+	}
+	#[test]
+	fn hillclimb_should_solve_n_eq_3() {
+		// Make a new state
+		let mut state =
+			State::new(vec![0, 0, 1, 0, 2, 0, 9, 0, 0], 3).unwrap();
+		// climb, asserting that the max score was reached
+		assert!(state.hillclimb());
+	}
+	#[test]
+	fn auto_solve_should_solve_n_eq_3() {
+		let mut state =
+			State::new(vec![0, 0, 1, 0, 2, 0, 9, 0, 0], 3).unwrap();
+		assert!(state.auto_solve());
+		assert_eq!(state.score(), state.max_score());
+	}
+	#[test]
+	fn auto_solve_should_solve_n_eq_4() {
+		let mut state = State::new(
+			vec![0, 0, 0, 0, 0, 3, 4, 0, 0, 0, 0, 0, 12, 0, 0, 0],
+			4,
+		)
+		.unwrap();
+		assert!(state.auto_solve());
+		assert_eq!(state.score(), state.max_score());
+	}
+	#[test]
+	fn auto_solve_should_solve_n_eq_8() {
+		// The benchmark board with a row and a half blanked out
+		#[rustfmt::skip]
+		#[allow(clippy::zero_prefixed_literal)]
+		let mut state = State::new(vec![
+			08, 07, 06, 05, 04, 03, 02, 01,
+			09, 00, 11, 12, 00, 14, 15, 16,
+			24, 23, 00, 21, 20, 19, 00, 17,
+			25, 26, 27, 00, 29, 30, 31, 32,
+			40, 00, 38, 37, 36, 00, 34, 33,
+			41, 42, 43, 44, 00, 46, 47, 48,
+			56, 00, 54, 53, 52, 51, 00, 49,
+			57, 58, 00, 60, 61, 00, 63, 64,
+		], 8).unwrap();
+		assert!(state.auto_solve());
+		assert_eq!(state.score(), state.max_score());
+	}
+	#[test]
+	fn hillclimb_should_satisfy_constraints() {
+		// 1 2 3
+		// 4 5 6
+		// 7 8 9 starts with 1 and 9 apart
+		let mut state = State::new(vec![0; 9], 3).unwrap();
+		state.add_constraint(1, 9);
+		assert_eq!(state.max_score(), 9);
+		assert_eq!(state.score(), 6);
+		assert!(state.hillclimb());
+		let one = state.board.iter().position(|&v| v == 1).unwrap();
+		let nine = state.board.iter().position(|&v| v == 9).unwrap();
+		assert_eq!(state.king_distance(one, nine), 1);
+	}
+	#[test]
+	fn step_budget_should_match_step() {
+		for _ in 0..20 {
+			let mut state = State::new_empty(4).unwrap();
+			let score = state.random_start();
+			let mut stepped = state.clone();
+			let high_score = stepped.step(score);
+			let mut calls = 0;
+			let result = loop {
+				calls += 1;
+				match state.step_budget(7) {
+					StepResult::Pending => continue,
+					StepResult::Done(score) => break score,
+				}
+			};
+			assert_eq!(result, high_score);
+			assert_eq!(state.board, stepped.board);
+			// a full scan of the 120 swaps takes many calls, unless
+			// a solving swap cut it short
+			assert!(calls > 1 || high_score == state.max_score());
+		}
+	}
+	#[test]
+	fn hillclimb_should_respect_restrictions() {
+		let puzzle =
+			State::new(vec![0, 0, 1, 0, 2, 0, 9, 0, 0], 3).unwrap();
+		for seed in 0..50 {
+			seed_rng(seed);
+			let mut state = puzzle.clone();
+			state.restrict(0, &[4]);
+			assert!(state.hillclimb());
+			assert_eq!(state.board[0], 4);
+			assert!(state.is_solved());
+		}
+		// a walk, but with 6 where 4 is required
+		let mut state = puzzle;
+		state.restrict(0, &[4]);
+		state.set_board(vec![6, 5, 1, 7, 2, 4, 9, 8, 3]);
+		assert_eq!(state.score(), state.max_score());
+		assert!(!state.is_solved());
+	}
+	#[test]
+	fn solvers_should_not_stop_on_a_walk_breaking_a_restriction() {
+		let mut puzzle =
+			State::new(vec![0, 0, 1, 0, 2, 0, 9, 0, 0], 3).unwrap();
+		puzzle.restrict(0, &[4]);
+		// a walk, but with 6 where 4 is required
+		puzzle.set_board(vec![6, 5, 1, 7, 2, 4, 9, 8, 3]);
+		assert_eq!(puzzle.score(), puzzle.max_score());
+		// with no restarts nothing can move off the walk
+		assert!(!puzzle.clone().hillclimb_trace(0).0);
+		assert!(!puzzle.clone().solve_record(0).0);
+		assert!(!puzzle.clone().solve_explained(0).0);
+		seed_rng(1);
+		let mut state = puzzle.clone();
+		assert_eq!(state.beam_search(4, 100), state.is_solved());
+		assert_eq!(state.board[0], 4);
+		let mut state = puzzle;
+		assert!(state.update_clue(2, Some(1)));
+		assert!(state.is_solved());
+	}
+	#[test]
+	fn step_should_respect_restrictions() {
+		let mut state = State::new_empty(3).unwrap();
+		state.restrict(4, &[5]);
+		for _ in 0..50 {
+			let mut score = state.random_start();
+			loop {
+				let before = state.board[4];
+				let round = state.step(score);
+				if state.board[4] != before {
+					assert_eq!(state.board[4], 5);
+				}
+				if round <= score {
+					break;
+				}
+				score = round;
+			}
+		}
+	}
+	#[test]
+	fn max_score_should_count_every_needed_link() {
+		// 8 links join the 9 cells of a 3x3 walk
+		let plain = State::new_empty(3).unwrap();
+		assert_eq!(plain.max_score(), 8);
+		// each constraint needs one more
+		let mut constrained = plain.clone();
+		constrained.add_constraint(1, 9);
+		assert_eq!(constrained.max_score(), 9);
+		// each break one fewer
+		let mut segmented =
+			State::with_segments(vec![0; 9], 3, &[3, 6]).unwrap();
+		assert_eq!(segmented.max_score(), 6);
+		segmented.add_constraint(1, 9);
+		assert_eq!(segmented.max_score(), 7);
+		// and hillclimbing stops once it is reached
+		for mut state in [plain, constrained, segmented] {
+			assert!(!state.is_solved());
+			assert!(state.hillclimb());
+			assert!(state.is_solved());
+			assert_eq!(state.score(), state.max_score());
+		}
+	}
+	#[test]
+	fn with_segments_should_drop_links_across_breaks() {
+		let mut board = vec![0; 16];
+		// 8 and 9 sit in opposite corners, too far apart for one walk
+		board[0] = 8;
+		board[15] = 9;
+		let mut state = State::new(board.clone(), 4).unwrap();
+		assert!(!state.solve_exact());
+		let mut state = State::with_segments(board, 4, &[8]).unwrap();
+		assert_eq!(
+			state.max_score(),
+			State::new_empty(4).unwrap().max_score() - 1
+		);
+		assert!(state.solve_exact());
+		assert_eq!(state.score(), state.max_score());
+		assert!(state.clone().solve_hamiltonian());
+		// a link across a break doesn't count even when made
+		let rows: Vec<u8> = (1..=16).collect();
+		let state = State::new(rows.clone(), 4).unwrap();
+		assert_eq!(state.score(), 12);
+		let state = State::with_segments(rows, 4, &[2]).unwrap();
+		assert_eq!(state.score(), 11);
+		assert_eq!(
+			State::with_segments(vec![0; 16], 4, &[16]).unwrap_err(),
+			KingsWalkError::InvalidBreak(16)
+		);
+	}
+	#[test]
+	fn min_defects_should_find_the_best_effort() {
+		// 1 and 2 are fixed in opposite corners so the link between
+		// them is the only one that can't be made
+		for _ in 0..10 {
+			let mut state =
+				State::new(vec![1, 0, 0, 0, 0, 0, 0, 0, 2], 3)
+					.unwrap();
+			assert_eq!(state.min_defects(200), 1);
+			assert_eq!(state.score(), state.max_score() - 1);
+		}
+	}
+	#[test]
+	fn solve_with_progress_should_report_rising_progress() {
+		let state = State::new(
+			vec![0, 0, 0, 0, 0, 3, 4, 0, 0, 0, 0, 0, 12, 0, 0, 0],
+			4,
+		)
+		.unwrap();
+		let (progress, handle) =
+			state.solve_with_progress(usize::MAX);
+		// the loop ends once the channel closes
+		let fractions: Vec<f64> = progress.iter().collect();
+		let (solved, state) = handle.join().unwrap();
+		assert!(solved);
+		assert_eq!(state.completion_fraction(), 1.0);
+		assert!(fractions.windows(2).all(|w| w[0] <= w[1]));
+		assert_eq!(fractions.last(), Some(&1.0));
+	}
+	#[test]
+	fn is_solvable_should_accept_a_solvable_board() {
+		let state =
+			State::new(vec![0, 0, 1, 0, 2, 0, 9, 0, 0], 3).unwrap();
+		assert!(state.is_solvable());
+	}
+	#[test]
+	fn conflicting_clues_should_find_a_walled_in_clue() {
+		// 1 5 .
+		// 6 7 .
+		// . . . leaves no free cell beside 1 for 2
+		let state =
+			State::new(vec![1, 5, 0, 6, 7, 0, 0, 0, 0], 3).unwrap();
+		assert_eq!(state.conflicting_clues(), vec![0]);
+		assert_eq!(state.upper_bound(), state.max_score() - 1);
+		assert!(!state.is_solvable());
+		// while a solvable puzzle has none
+		let state =
+			State::new(vec![0, 0, 1, 0, 2, 0, 9, 0, 0], 3).unwrap();
+		assert!(state.conflicting_clues().is_empty());
+		assert_eq!(state.upper_bound(), state.max_score());
+		// consecutive clues apart are both reported
+		let state =
+			State::new(vec![1, 0, 0, 0, 0, 0, 0, 0, 2], 3).unwrap();
+		assert_eq!(state.conflicting_clues(), vec![0, 8]);
+	}
+	#[test]
+	fn is_solvable_should_reject_clues_too_far_apart() {
+		// 1 and 2 are fixed in opposite corners
+		let state =
+			State::new(vec![1, 0, 0, 0, 0, 0, 0, 0, 2], 3).unwrap();
+		assert!(!state.is_solvable());
+		// and again on a board too large for the exact solver, with
+		// 5 needing to sit next to both 4 and 6
+		let mut board = vec![0; 36];
+		board[0] = 4;
+		board[2] = 6;
+		board[35] = 5;
+		let state = State::new(board, 6).unwrap();
+		assert!(!state.clues_reachable());
+		assert!(!state.is_solvable());
+	}
+	#[test]
+	fn minimize_clues_should_keep_the_puzzle_solvable() {
+		// start from a solved board where every cell is fixed
+		let mut state =
+			State::new(vec![3, 4, 1, 8, 2, 5, 9, 7, 6], 3).unwrap();
+		state.minimize_clues(3);
+		let clues = state.board.len() - state.assignments.len();
+		assert!(clues < 9);
+		state.random_start();
+		assert!(state.hillclimb());
+	}
+	#[test]
+	fn adjacency_list_should_count_king_moves() {
+		let state = State::new(vec![0; 16], 4).unwrap();
+		let adjacency = state.adjacency_list();
+		assert_eq!(adjacency.len(), 16);
+		// corners
+		for &idx in &[0, 3, 12, 15] {
+			assert_eq!(adjacency[idx].len(), 3);
+		}
+		// edges
+		for &idx in &[1, 2, 4, 7, 8, 11, 13, 14] {
+			assert_eq!(adjacency[idx].len(), 5);
+		}
+		// interior
+		for &idx in &[5, 6, 9, 10] {
+			assert_eq!(adjacency[idx].len(), 8);
+		}
+		assert_eq!(adjacency[0], vec![1, 4, 5]);
+	}
+	#[test]
+	fn solve_explained_should_log_each_move() {
+		let mut state = State::new(
+			vec![0, 0, 0, 0, 0, 3, 4, 0, 0, 0, 0, 0, 12, 0, 0, 0],
+			4,
+		)
+		.unwrap();
+		let (solved, log) = state.solve_explained(usize::MAX);
+		assert!(solved);
+		assert_eq!(log.last().map(String::as_str), Some("Solved!"));
+		// every swap starts from the score the line before ended on
+		// and raises it
+		let mut score = None;
+		for line in &log[..log.len() - 1] {
+			let scores = line.rsplit("score ").next().unwrap();
+			let scores: Vec<usize> = scores
+				.split('\u{2192}')
+				.map(|x| x.parse().unwrap())
+				.collect();
+			if line.starts_with("Restart #") {
+				assert_eq!(scores.len(), 1);
+			} else {
+				assert!(line.starts_with("Swapped cells ("));
+				assert_eq!(Some(scores[0]), score);
+				assert!(scores[1] > scores[0]);
+			}
+			score = scores.last().copied();
+		}
+		assert_eq!(score, Some(state.max_score()));
+	}
+	#[test]
+	fn step_should_work() {
+		// Make a new state
+		let mut state =
+			State::new(vec![0, 0, 1, 0, 2, 0, 9, 0, 0], 3).unwrap();
+		// Ensure it initialized as expected
+		assert_eq!(vec![3, 4, 1, 5, 2, 6, 9, 7, 8], state.board);
+		// Calculate the starting score
+		let start_score = state.score();
+		// Find a better assignment
+		let end_score = state.step(start_score);
+		// Ensure it was better
+		assert!(start_score < end_score);
+		// See that the board is arranged as expected.
+		// 			start	end
+		// 			3 4 1	3 5 1
+		// 			5 2 6	4 2 6
+		//			9 7 8	9 7 8
+		// score:	7		6
+		assert_eq!(state.board, vec![3, 5, 1, 4, 2, 6, 9, 7, 8]);
+		assert_eq!(end_score, 7);
+	}
+	#[test]
+	fn step_should_break_ties_toward_the_smallest_swap() {
+		// 3 4 1
+		// 5 2 6
+		// 9 7 8 has four swaps reaching 7, the best there is
+		let mut state =
+			State::new(vec![0, 0, 1, 0, 2, 0, 9, 0, 0], 3).unwrap();
+		for &(idx1, idx2) in &[(1, 3), (3, 8), (5, 7), (7, 8)] {
+			let mut swapped = state.clone();
+			swapped.swap(idx1, idx2);
+			assert_eq!(swapped.score(), 7);
+		}
+		let start_score = state.score();
+		assert_eq!(state.step(start_score), 7);
+		// (1, 3) is the one taken
+		assert_eq!(state.board, vec![3, 5, 1, 4, 2, 6, 9, 7, 8]);
+	}
+	#[test]
+	fn step_should_stop_at_a_solving_swap() {
+		let mut state = State::new(vec![0; 16], 4).unwrap();
+		// a solved board with its first two cells swapped
+		state.set_board(vec![
+			2, 1, 3, 4, 8, 7, 6, 5, 9, 10, 11, 12, 16, 15, 14, 13,
+		]);
+		let start_score = state.score();
+		CANDIDATES.with(|c| c.set(0));
+		let end_score = state.step(start_score);
+		assert_eq!(end_score, state.max_score());
+		assert_eq!(&state.board[..2], &[1, 2]);
+		// the very first of the 120 candidate swaps solves it
+		assert_eq!(CANDIDATES.with(Cell::get), 1);
+	}
+	#[test]
+	fn step_with_mode_should_reach_a_local_maximum() {
+		for &mode in
+			&[StepMode::BestImprovement, StepMode::FirstImprovement]
+		{
+			let mut state = State::new(
+				vec![0, 0, 0, 0, 0, 3, 4, 0, 0, 0, 0, 0, 12, 0, 0, 0],
+				4,
+			)
+			.unwrap();
+			let mut score = state.random_start();
+			loop {
+				let next = state.step_with_mode(score, mode);
+				assert!(next >= score);
+				assert_eq!(next, state.score());
+				if next == score {
+					break;
+				}
+				score = next;
+			}
+			// no single swap raises the score any more
+			assert_eq!(state.best_swap(score), None);
+		}
+	}
+	#[test]
+	fn step_with_mode_should_take_the_first_improvement() {
+		let mut state =
+			State::new(vec![0, 0, 1, 0, 2, 0, 9, 0, 0], 3).unwrap();
+		let start_score = state.score();
+		let mode = StepMode::FirstImprovement;
+		CANDIDATES.with(|c| c.set(0));
+		let end_score = state.step_with_mode(start_score, mode);
+		assert!(end_score > start_score);
+		// the 6th of the 15 swaps, 4 with 5, is the first that helps
+		assert_eq!(CANDIDATES.with(Cell::get), 6);
+		assert_eq!(state.board, vec![3, 5, 1, 4, 2, 6, 9, 7, 8]);
+	}
+	#[test]
+	fn score_should_work2() {
+		// Make a new state
+		let state =
+			State::new(vec![3, 4, 1, 8, 2, 5, 9, 7, 6], 3).unwrap();
+		// 3 4 1
+		// 8 2 5
+		// 9 7 6
+		// total: 8
+		assert_eq!(state.score(), state.max_score());
+	}
+	#[test]
+	fn render_arrows_should_follow_the_walk() {
+		let state =
+			State::new(vec![1, 2, 3, 6, 5, 4, 7, 8, 9], 3).unwrap();
+		let arrows = state.render_arrows();
+		assert_eq!(arrows, "1→ 2→ 3↓\n6↓ 5← 4←\n7→ 8→ 9\n");
+		assert!(!arrows.contains('·'));
+		// 3 and 4 are apart, as are 6 and 7
+		let state =
+			State::new(vec![1, 2, 3, 4, 5, 6, 7, 8, 9], 3).unwrap();
+		assert_eq!(
+			state.render_arrows(),
+			"1→ 2→ 3·\n4→ 5→ 6·\n7→ 8→ 9\n"
+		);
+	}
+	#[test]
+	fn inversions_should_count_pairs_out_of_order() {
+		let solved = vec![1, 2, 3, 6, 5, 4, 7, 8, 9];
+		let state = State::new(solved.clone(), 3).unwrap();
+		// 6 5 4 is the only stretch out of order
+		assert_eq!(state.inversions(), 3);
+		let reversed: Vec<u8> = solved.into_iter().rev().collect();
+		let state = State::new(reversed, 3).unwrap();
+		// every other of the 36 pairs
+		assert_eq!(state.inversions(), 33);
+	}
+	#[test]
+	fn score_matrix_should_count_links_per_cell() {
+		let state =
+			State::new(vec![1, 2, 3, 6, 5, 4, 7, 8, 9], 3).unwrap();
+		let matrix = state.score_matrix();
+		// the ends of the walk sit in two corners, every other cell
+		// is linked both ways
+		assert_eq!(
+			matrix,
+			vec![vec![1, 2, 2], vec![2, 2, 2], vec![2, 2, 1]]
+		);
+		let total: usize = matrix
+			.iter()
+			.flatten()
+			.map(|&links| links as usize)
+			.sum();
+		assert_eq!(total / 2, state.score());
+	}
+	#[test]
+	fn value_links_should_count_each_link_twice() {
+		// 3 4 1
+		// 8 2 5
+		// 9 7 6
+		let state =
+			State::new(vec![3, 4, 1, 8, 2, 5, 9, 7, 6], 3).unwrap();
+		// the ends of the walk have one link, the rest two
+		assert_eq!(state.value_links(1), 1);
+		assert_eq!(state.value_links(9), 1);
+		for v in 2..=8 {
+			assert_eq!(state.value_links(v), 2);
+		}
+		let total: usize =
+			(1..=9).map(|v| state.value_links(v)).sum();
+		assert_eq!(total, 2 * state.score());
+	}
+	#[test]
+	fn score_should_work1() {
+		// Make a new state
+		let state =
+			State::new(vec![0, 0, 1, 0, 2, 0, 9, 0, 0], 3).unwrap();
+		// ensure the board is properly setup
+		assert_eq!(vec![3, 4, 1, 5, 2, 6, 9, 7, 8], state.board);
+		// 3 4 1	1-2-3-4-5 	score: 4
+		// 5 2 6	6-7-8		score: 2
+		// 9 7 8	9			score: 0
+		// 						total: 6
+		assert_eq!(state.score(), 6);
+	}
+	#[test]
+	fn random_start_should_only_have_unique_values() {
+		// Make a new state
+		let mut state =
+			State::new(vec![0, 0, 1, 0, 2, 0, 9, 0, 0], 3).unwrap();
+		// Randomize it
+		state.random_start();
+		// ensure each value is unique and in the range [1,n*n]
+		let mut seen = HashSet::new();
+		for x in &state.board {
+			assert!(!seen.contains(x));
+			assert!(*x > 0 && *x <= 9);
+			seen.insert(*x);
+		}
+	}
+	#[test]
+	fn random_start_should_move_every_free_cell() {
+		// With two free cells both orders must eventually show up
+		let mut state =
+			State::new(vec![0, 0, 3, 4, 5, 6, 7, 8, 9], 3).unwrap();
+		let mut seen = HashSet::new();
+		for _ in 0..64 {
+			state.random_start();
+			seen.insert(state.board.clone());
+		}
+		assert_eq!(seen.len(), 2);
+	}
+	#[test]
+	fn new_should_reject_invalid_values() {
+		assert_eq!(
+			State::new(vec![0, 0, 1, 0, 2, 0, 10, 0, 0], 3)
+				.unwrap_err(),
+			KingsWalkError::ValueOutOfRange(6)
+		);
+		assert_eq!(
+			State::new(vec![0, 0, 1, 0, 2, 0, 1, 0, 0], 3)
+				.unwrap_err(),
+			KingsWalkError::DuplicateValue(6)
+		);
+		assert_eq!(
+			State::new(vec![], 0).unwrap_err(),
+			KingsWalkError::BoardLength
+		);
+		assert_eq!(
+			State::new(vec![0; 256], 16).unwrap_err(),
+			KingsWalkError::BoardTooLarge
+		);
+		assert_eq!(
+			State::new(vec![0; 16], usize::MAX).unwrap_err(),
+			KingsWalkError::BoardTooLarge
+		);
+		assert!(State::new(vec![0; 225], MAX_N_U8).is_ok());
+	}
+	#[test]
+	fn from_iter_n_should_match_new() {
+		let board = vec![0, 0, 1, 0, 2, 0, 9, 0, 0];
+		let state =
+			State::from_iter_n(board.iter().copied(), 3).unwrap();
+		assert_eq!(state.board, State::new(board, 3).unwrap().board);
+		let values = "0 0 1 0 2 0 9 0 0"
+			.split(' ')
+			.map(|token| token.parse().unwrap());
+		assert!(State::from_iter_n(values, 3).is_ok());
+		assert_eq!(
+			State::from_iter_n(0..8, 3).unwrap_err(),
+			KingsWalkError::BoardLength
+		);
+		// an endless iterator is cut off
+		assert_eq!(
+			State::from_iter_n(std::iter::repeat(0), 3).unwrap_err(),
+			KingsWalkError::BoardLength
+		);
+	}
+	#[test]
+	fn as_ref_should_be_the_board() {
+		let state =
+			State::new(vec![0, 0, 1, 0, 2, 0, 9, 0, 0], 3).unwrap();
+		let board: &[u8] = state.as_ref();
+		assert_eq!(board.len(), 9);
+		assert_eq!(board, &state.board[..]);
+	}
+	#[test]
+	fn blank_density_should_be_the_free_fraction() {
+		let state =
+			State::new(vec![0, 0, 1, 0, 2, 0, 9, 0, 0], 3).unwrap();
+		assert_eq!(state.blank_density(), 6.0 / 9.0);
+		assert_eq!(State::default().blank_density(), 1.0);
+	}
+	#[test]
+	fn state_should_be_send_and_sync() {
+		fn assert_send<T: Send>() {}
+		fn assert_sync<T: Sync>() {}
+		assert_send::<State>();
+		assert_sync::<State>();
+	}
+	#[test]
+	fn default_should_be_an_empty_3x3() {
+		let state = State::default();
+		assert_eq!(state.dimension(), 3);
+		assert_eq!(state.free_cells().count(), 9);
+		assert!(state.is_solvable());
+		assert_eq!(
+			State::new_empty(usize::MAX).unwrap_err(),
+			KingsWalkError::BoardTooLarge
+		);
+	}
+	#[test]
+	fn from_str_should_parse_a_grid() {
+		let state: State = "# the 3x3 fixture\n0 0 1\n. 2 .\n\n9 0 \
+		                    0\n"
+		.parse()
+		.unwrap();
+		assert_eq!(state.n, 3);
+		assert_eq!(vec![3, 4, 1, 5, 2, 6, 9, 7, 8], state.board);
+	}
+	#[test]
+	fn to_puzzle_string_should_parse_back() {
+		let state =
+			State::new(vec![0, 0, 1, 0, 2, 0, 9, 0, 0], 3).unwrap();
+		let text = state.to_puzzle_string();
+		assert_eq!(text, ". . 1\n. 2 .\n9 . .\n");
+		let parsed: State = text.parse().unwrap();
+		assert_eq!(parsed.board, state.board);
+		assert_eq!(parsed.assignments, state.assignments);
+	}
+	#[test]
+	fn from_str_should_reject_bad_grids() {
+		// ragged rows
+		assert_eq!(
+			"0 0 1\n0 2\n9 0 0".parse::<State>().unwrap_err(),
+			KingsWalkError::BoardLength
+		);
+		// a ragged row with a bad cell in it
+		assert_eq!(
+			"0 0 0 x\n0 0 0\n0 0 0".parse::<State>().unwrap_err(),
+			KingsWalkError::BoardLength
+		);
+		// not a number
+		assert_eq!(
+			"0 0 1\n0 x 0\n9 0 0".parse::<State>().unwrap_err(),
+			KingsWalkError::InvalidCell(4)
+		);
+		// nothing to parse
+		assert_eq!(
+			"".parse::<State>().unwrap_err(),
+			KingsWalkError::BoardLength
+		);
+	}
+	#[test]
+	fn set_row_should_keep_a_permutation() {
+		let mut state =
+			State::new(vec![0, 0, 1, 0, 2, 0, 9, 0, 0], 3).unwrap();
+		state.set_row(1, &[8, 2, 3]).unwrap();
+		assert_eq!(&state.board[3..6], &[8, 2, 3]);
+		assert!(is_permutation(&state.board));
+		// the fixed cells stay put
+		assert_eq!(state.board[2], 1);
+		assert_eq!(state.board[6], 9);
+	}
+	#[test]
+	fn require_endpoints_should_pin_the_walk() {
+		let mut state = State::new(vec![0; 16], 4).unwrap();
+		state.require_endpoints((0, 0), (3, 3)).unwrap();
+		assert_eq!(state.assignments.len(), 14);
+		assert!(state.hillclimb());
+		assert_eq!(state.value_at(0, 0), Some(1));
+		assert_eq!(state.value_at(3, 3), Some(16));
+	}
+	#[test]
+	fn update_clue_should_solve_again() {
+		seed_rng(9);
+		let mut state = State::new_empty(4).unwrap();
+		assert!(state.hillclimb());
+		for _ in 0..3 {
+			assert!(state.update_clue(5, Some(1)));
+			assert_eq!(state.value_at(1, 1), Some(1));
+			assert_eq!(state.assignments.len(), 15);
+			assert!(state.is_solved());
+			assert!(state.update_clue(10, Some(16)));
+			assert_eq!(state.assignments.len(), 14);
+			assert!(state.is_solved());
+			assert!(state.update_clue(5, None));
+			assert!(state.update_clue(10, None));
+			assert_eq!(state.assignments.len(), 16);
+			assert!(state.is_solved());
+			assert!(state.positions_consistent());
+		}
+	}
+	#[test]
+	fn update_clue_should_reject_bad_clues() {
+		let mut state =
+			State::new(vec![0, 0, 1, 0, 2, 0, 9, 0, 0], 3).unwrap();
+		let before = state.clone();
+		// off the board, out of range, and 9 is a clue elsewhere
+		for &(idx, value) in
+			&[(9, Some(3)), (0, Some(0)), (0, Some(10)), (0, Some(9))]
+		{
+			assert!(!state.update_clue(idx, value));
+			assert_eq!(state.board, before.board);
+			assert_eq!(state.assignments, before.assignments);
+		}
+		// changing a clue in place frees the value it held
+		assert!(state.update_clue(2, Some(3)));
+		assert_eq!(state.board[2], 3);
+		assert!(state.assignments.binary_search(&2).is_err());
+		assert!(state.is_solved());
+		// 2 can't follow 1 from the far corner
+		let mut state = State::new_empty(3).unwrap();
+		assert!(state.update_clue(0, Some(1)));
+		assert!(!state.update_clue(8, Some(2)));
+		assert!(state.positions_consistent());
+	}
+	#[test]
+	fn with_path_prefix_should_start_the_walk() {
+		let mut state = State::new_empty(4).unwrap();
+		let prefix = [(0, 0), (1, 1), (0, 2)];
+		state.with_path_prefix(&prefix).unwrap();
+		assert_eq!(state.assignments.len(), 13);
+		assert!(state.solve_exact());
+		for (step, &(row, col)) in prefix.iter().enumerate() {
+			assert_eq!(
+				state.value_at(row, col),
+				Some(step as u8 + 1)
+			);
+		}
+		assert!(state.is_solved());
+		// a prefix can run through clues that agree with it
+		let mut state =
+			State::new(vec![0, 0, 1, 0, 2, 0, 9, 0, 0], 3).unwrap();
+		state.with_path_prefix(&[(0, 2), (1, 1), (1, 0)]).unwrap();
+		assert_eq!(state.value_at(1, 0), Some(3));
+		assert!(state.hillclimb());
+	}
+	#[test]
+	fn with_path_prefix_should_reject_bad_paths() {
+		let mut state =
+			State::new(vec![0, 0, 1, 0, 2, 0, 9, 0, 0], 3).unwrap();
+		let before = state.clone();
+		let rejected = [
+			// 1 is fixed at (0, 2), and 2 at (1, 1)
+			(vec![(0, 0)], KingsWalkError::FixedCell(2)),
+			(vec![(0, 2), (0, 1)], KingsWalkError::FixedCell(4)),
+			(
+				vec![(0, 2), (1, 1), (1, 3)],
+				KingsWalkError::InvalidPosition(1, 3),
+			),
+			(
+				vec![(0, 2), (1, 1), (0, 2)],
+				KingsWalkError::DuplicateValue(2),
+			),
+			(
+				vec![(0, 2), (1, 1), (1, 0), (0, 2)],
+				KingsWalkError::DuplicateValue(2),
+			),
+			(
+				vec![(0, 2), (1, 1), (1, 0), (2, 2)],
+				KingsWalkError::InvalidStep(3),
+			),
+		];
+		for (prefix, err) in rejected {
+			assert_eq!(
+				state.with_path_prefix(&prefix).unwrap_err(),
+				err
+			);
+			assert_eq!(state.board, before.board);
+			assert_eq!(state.assignments, before.assignments);
+		}
+		// across a break the walk may jump
+		let mut state =
+			State::with_segments(vec![0; 9], 3, &[1]).unwrap();
+		state.with_path_prefix(&[(0, 0), (2, 2)]).unwrap();
+	}
+	#[test]
+	fn require_endpoints_should_reject_fixed_cells() {
+		let mut state =
+			State::new(vec![0, 0, 1, 0, 2, 0, 9, 0, 0], 3).unwrap();
+		// 1 is already fixed
+		assert_eq!(
+			state.require_endpoints((0, 0), (2, 2)).unwrap_err(),
+			KingsWalkError::FixedCell(2)
+		);
+		let mut state = State::new(vec![0; 9], 3).unwrap();
+		assert_eq!(
+			state.require_endpoints((1, 1), (1, 1)).unwrap_err(),
+			KingsWalkError::DuplicateValue(4)
+		);
+		assert_eq!(
+			state.require_endpoints((0, 0), (0, 3)).unwrap_err(),
+			KingsWalkError::InvalidPosition(0, 3)
+		);
+		assert_eq!(state.assignments.len(), 9);
+	}
+	#[test]
+	fn try_place_should_move_free_values() {
+		let mut state =
+			State::new(vec![0, 0, 1, 0, 2, 0, 9, 0, 0], 3).unwrap();
+		// 3 4 1
+		// 5 2 6
+		// 9 7 8
+		state.try_place(0, 0, 8).unwrap();
+		assert_eq!(state.board, vec![8, 4, 1, 5, 2, 6, 9, 7, 3]);
+		assert_eq!(
+			state.try_place(0, 2, 4).unwrap_err(),
+			KingsWalkError::FixedCell(2)
+		);
+		assert_eq!(
+			state.try_place(0, 0, 9).unwrap_err(),
+			KingsWalkError::FixedCell(6)
+		);
+		assert_eq!(
+			state.try_place(3, 0, 4).unwrap_err(),
+			KingsWalkError::InvalidPosition(3, 0)
+		);
+		assert_eq!(
+			state.try_place(0, 0, 10).unwrap_err(),
+			KingsWalkError::ValueOutOfRange(0)
+		);
+	}
+	#[test]
+	fn hint_should_suggest_the_step() {
+		let mut state =
+			State::new(vec![0, 0, 1, 0, 2, 0, 9, 0, 0], 3).unwrap();
+		// step_should_work swaps the 4 and the 5
+		assert_eq!(state.hint(), Some(((0, 1), (1, 0))));
+		let mut solved =
+			State::new(vec![3, 4, 1, 8, 2, 5, 9, 7, 6], 3).unwrap();
+		assert_eq!(solved.hint(), None);
+	}
+	#[test]
+	fn set_row_should_reject_bad_rows() {
+		let mut state =
+			State::new(vec![0, 0, 1, 0, 2, 0, 9, 0, 0], 3).unwrap();
+		let before = state.board.clone();
+		assert_eq!(
+			state.set_row(3, &[3, 4, 5]).unwrap_err(),
+			KingsWalkError::InvalidRow(3)
+		);
+		assert_eq!(
+			state.set_row(1, &[3, 2]).unwrap_err(),
+			KingsWalkError::BoardLength
+		);
+		// overwriting the fixed 2
+		assert_eq!(
+			state.set_row(1, &[3, 4, 5]).unwrap_err(),
+			KingsWalkError::FixedCell(4)
+		);
+		// taking the fixed 9 away from its cell
+		assert_eq!(
+			state.set_row(0, &[9, 3, 1]).unwrap_err(),
+			KingsWalkError::FixedCell(6)
+		);
+		assert_eq!(
+			state.set_row(2, &[9, 4, 4]).unwrap_err(),
+			KingsWalkError::DuplicateValue(8)
+		);
+		assert_eq!(before, state.board);
+	}
+	#[test]
+	fn to_puzzle_should_undo_solving() {
+		let board = vec![0, 0, 1, 0, 2, 0, 9, 0, 0];
+		let mut state = State::new(board.clone(), 3).unwrap();
+		assert!(state.hillclimb());
+		let puzzle = state.to_puzzle();
+		assert_eq!(puzzle.board, State::new(board, 3).unwrap().board);
+		assert_eq!(puzzle.assignments, state.assignments);
+	}
+	#[test]
+	fn value_at_and_position_of_should_agree() {
+		// 3 4 1
+		// 8 2 5
+		// 9 7 6
+		let mut state =
+			State::new(vec![3, 4, 1, 8, 2, 5, 9, 7, 6], 3).unwrap();
+		assert_eq!(state.value_at(0, 2), Some(1));
+		assert_eq!(state.value_at(2, 0), Some(9));
+		assert_eq!(state.position_of(1), Some((0, 2)));
+		assert_eq!(state.position_of(7), Some((2, 1)));
+		for v in 1..=9 {
+			let (row, col) = state.position_of(v).unwrap();
+			assert_eq!(state.value_at(row, col), Some(v));
+		}
+		// and still after the board changes
+		state.swap(0, 8);
+		assert_eq!(state.position_of(3), Some((2, 2)));
+		assert_eq!(state.position_of(6), Some((0, 0)));
+	}
+	#[test]
+	fn value_at_and_position_of_should_check_bounds() {
+		let state =
+			State::new(vec![3, 4, 1, 8, 2, 5, 9, 7, 6], 3).unwrap();
+		assert_eq!(state.value_at(3, 0), None);
+		assert_eq!(state.value_at(0, 3), None);
+		assert_eq!(state.position_of(0), None);
+		assert_eq!(state.position_of(10), None);
+	}
+	#[test]
+	fn free_cells_should_match_the_zeros() {
+		let state =
+			State::new(vec![0, 0, 1, 0, 2, 0, 9, 0, 0], 3).unwrap();
+		let cells: Vec<(usize, usize)> = state.free_cells().collect();
+		assert_eq!(
+			cells,
+			vec![(0, 0), (0, 1), (1, 0), (1, 2), (2, 1), (2, 2)]
+		);
+	}
+	#[test]
+	fn into_answer_key_should_need_a_solved_board() {
+		let mut state =
+			State::new(vec![0, 0, 1, 0, 2, 0, 9, 0, 0], 3).unwrap();
+		assert_eq!(
+			state.clone().into_answer_key().unwrap_err(),
+			KingsWalkError::NotSolved
+		);
+		assert!(state.solve_exact());
+		let board = state.board.clone();
+		assert_eq!(state.into_answer_key().unwrap(), board);
+	}
+	#[test]
+	fn reveal_order_should_follow_the_walk() {
+		// 1 . 3     1 2 3
+		// . 5 .  -> 6 5 4
+		// 7 . 9     7 8 9
+		let mut state =
+			State::new(vec![1, 0, 3, 0, 5, 0, 7, 0, 9], 3).unwrap();
+		assert!(state.reveal_order().is_empty());
+		assert!(state.solve_exact());
+		let order = state.reveal_order();
+		assert_eq!(order, vec![1, 5, 3, 7]);
+		let values: Vec<u8> =
+			order.iter().map(|&idx| state.board[idx]).collect();
+		assert_eq!(values, vec![2, 4, 6, 8]);
+	}
+	#[test]
+	fn with_blank_should_read_a_sentinel() {
+		let state = State::with_blank(
+			vec![255, 255, 1, 255, 2, 255, 9, 255, 255],
+			3,
+			255,
+		)
+		.unwrap();
+		assert_eq!(vec![3, 4, 1, 5, 2, 6, 9, 7, 8], state.board);
+		let state = State::parse_with_blank(
+			"255 . 1\n255 2 255\n9 255 255",
+			255,
+		)
+		.unwrap();
+		assert_eq!(vec![3, 4, 1, 5, 2, 6, 9, 7, 8], state.board);
+	}
+	#[test]
+	fn with_blank_should_reject_bad_sentinels() {
+		assert_eq!(
+			State::with_blank(vec![9, 9, 1, 9, 2, 9, 9, 9, 9], 3, 9)
+				.unwrap_err(),
+			KingsWalkError::InvalidBlank(9)
+		);
+		// with another blank a 0 is not a value
+		assert_eq!(
+			State::with_blank(
+				vec![255, 0, 1, 255, 2, 255, 9, 255, 255],
+				3,
+				255
+			)
+			.unwrap_err(),
+			KingsWalkError::ValueOutOfRange(1)
+		);
+	}
+	#[test]
+	fn new_states_should_only_have_unique_values() {
+		// Make a new state
+		let state =
+			State::new(vec![0, 0, 1, 0, 2, 0, 9, 0, 0], 3).unwrap();
+		// ensure the board is properly setup
+		assert_eq!(vec![3, 4, 1, 5, 2, 6, 9, 7, 8], state.board);
+	}
+
+	// A random board of size 2..=6 and a random subset of its cells
+	// blanked out
+	fn puzzles() -> impl Strategy<Value = (Vec<u8>, usize)> {
+		(2..=6usize).prop_flat_map(|n| {
+			let values: Vec<u8> = (1..=(n * n) as u8).collect();
+			(
+				Just(values).prop_shuffle(),
+				prop::collection::vec(any::<bool>(), n * n),
+			)
+				.prop_map(move |(values, blanks)| {
+					let board = values
+						.iter()
+						.zip(blanks)
+						.map(|(&v, blank)| if blank { 0 } else { v })
+						.collect();
+					(board, n)
+				})
+		})
+	}
+	fn is_permutation(board: &[u8]) -> bool {
+		let mut sorted = board.to_vec();
+		sorted.sort_unstable();
+		sorted.into_iter().eq(1..=board.len() as u8)
+	}
+	proptest! {
+		#[test]
+		fn swaps_should_keep_positions_consistent(
+			(board, n) in puzzles(),
+			swaps in prop::collection::vec(any::<(usize, usize)>(), 0..32)
+		) {
+			let mut state = State::new(board, n).unwrap();
+			prop_assert!(state.positions_consistent());
+			let cells = state.board.len();
+			for (idx1, idx2) in swaps {
+				state.swap(idx1 % cells, idx2 % cells);
+				prop_assert!(state.positions_consistent());
+			}
+		}
+		#[test]
+		fn new_should_make_a_permutation((board, n) in puzzles()) {
+			let state = State::new(board.clone(), n).unwrap();
+			prop_assert!(is_permutation(&state.board));
+			// and the fixed cells are left alone
+			for (&before, &after) in board.iter().zip(&state.board) {
+				prop_assert!(before == 0 || before == after);
+			}
+		}
+		#[test]
+		fn random_start_should_keep_the_values(
+			(board, n) in puzzles()
+		) {
+			let mut state = State::new(board.clone(), n).unwrap();
+			let before = state.board.clone();
+			let score = state.random_start();
+			prop_assert_eq!(score, state.score());
+			prop_assert!(is_permutation(&state.board));
+			prop_assert!(state.positions_consistent());
+			for (idx, &v) in board.iter().enumerate() {
+				if v != 0 {
+					prop_assert_eq!(before[idx], state.board[idx]);
+				}
+			}
+		}
+		#[test]
+		fn score_should_not_exceed_max_score(
+			(board, n) in puzzles()
+		) {
+			let mut state = State::new(board, n).unwrap();
+			prop_assert!(state.score() <= state.max_score());
+			state.random_start();
+			prop_assert!(state.score() <= state.max_score());
+		}
+		#[test]
+		fn step_should_not_decrease_the_score(
+			(board, n) in puzzles()
+		) {
+			let mut state = State::new(board, n).unwrap();
+			let start_score = state.score();
+			let end_score = state.step(start_score);
+			prop_assert!(end_score >= start_score);
+			prop_assert_eq!(end_score, state.score());
+			prop_assert!(is_permutation(&state.board));
+			prop_assert!(state.positions_consistent());
+		}
+	}
+}
@@ -0,0 +1,148 @@
+use super::State;
+
+// Number the cells of an n by n board given links, pairs of cells
+// that must hold consecutive values, without knowing which of each
+// pair comes first. The numbering is a king's walk through every cell
+// that steps along each link, found by backtracking. Returns None
+// when a link is out of range or no such walk exists. The cells of
+// the returned state are all free.
+pub fn solve_from_adjacency(
+	links: &[(usize, usize)],
+	n: usize,
+) -> Option<State> {
+	let mut state = State::new_empty(n).ok()?;
+	let adjacency = state.adjacency_list();
+	let cells = adjacency.len();
+	// the cells each cell must sit next to in the walk
+	let mut partners = vec![Vec::new(); cells];
+	for &(a, b) in links {
+		if a >= cells || !adjacency[a].contains(&b) {
+			return None;
+		}
+		if !partners[a].contains(&b) {
+			partners[a].push(b);
+			partners[b].push(a);
+		}
+	}
+	if partners.iter().any(|p| p.len() > 2) {
+		return None;
+	}
+	let mut used = vec![false; cells];
+	let mut path = Vec::with_capacity(cells);
+	// the walk can only start at a cell with a link to spare
+	for start in 0..cells {
+		if partners[start].len() == 2 {
+			continue;
+		}
+		used[start] = true;
+		path.push(start);
+		if extend(&adjacency, &partners, &mut used, &mut path) {
+			let mut board = vec![0; cells];
+			for (value, &idx) in path.iter().enumerate() {
+				board[idx] = value as u8 + 1;
+			}
+			state.set_board(board);
+			return Some(state);
+		}
+		path.pop();
+		used[start] = false;
+	}
+	None
+}
+
+// Try to continue path to the next cell
+fn extend(
+	adjacency: &[Vec<usize>],
+	partners: &[Vec<usize>],
+	used: &mut [bool],
+	path: &mut Vec<usize>,
+) -> bool {
+	if path.len() == used.len() {
+		return true;
+	}
+	let last = path[path.len() - 1];
+	let prev = path.len().checked_sub(2).map(|i| path[i]);
+	// a link not yet walked has to be walked next
+	let mut candidates: Vec<usize> = partners[last]
+		.iter()
+		.copied()
+		.filter(|&p| Some(p) != prev)
+		.collect();
+	if candidates.is_empty() {
+		// otherwise any free cell whose links still leave room for
+		// the step in from last
+		candidates = adjacency[last]
+			.iter()
+			.copied()
+			.filter(|&c| partners[c].len() < 2)
+			.collect();
+	}
+	candidates.retain(|&c| !used[c]);
+	// Warnsdorff's rule: the most cornered cells go first
+	candidates.sort_by_key(|&c| {
+		adjacency[c].iter().filter(|&&x| !used[x]).count()
+	});
+	for idx in candidates {
+		used[idx] = true;
+		path.push(idx);
+		if extend(adjacency, partners, used, path) {
+			return true;
+		}
+		path.pop();
+		used[idx] = false;
+	}
+	false
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// every link in links joins consecutive values of state
+	fn walks_links(state: &State, links: &[(usize, usize)]) -> bool {
+		links.iter().all(|&(a, b)| {
+			let (x, y) = (state.board[a], state.board[b]);
+			x + 1 == y || y + 1 == x
+		})
+	}
+
+	#[test]
+	fn solve_from_adjacency_should_recover_a_solved_board() {
+		let mut solved = State::new_empty(5).unwrap();
+		solved.solve_hamiltonian();
+		let path = solved.solution_path();
+		let links: Vec<_> =
+			path.windows(2).map(|pair| (pair[0], pair[1])).collect();
+		let state = solve_from_adjacency(&links, 5).unwrap();
+		assert!(state.is_solved());
+		assert!(walks_links(&state, &links));
+		// only the direction of the walk is lost
+		let reversed: Vec<u8> =
+			solved.board.iter().map(|&v| 26 - v).collect();
+		assert!(
+			state.board == solved.board || state.board == reversed
+		);
+	}
+
+	#[test]
+	fn solve_from_adjacency_should_fill_in_missing_links() {
+		let links = [(0, 4), (4, 8), (2, 5)];
+		let state = solve_from_adjacency(&links, 3).unwrap();
+		assert!(state.is_solved());
+		assert!(walks_links(&state, &links));
+	}
+
+	#[test]
+	fn solve_from_adjacency_should_reject_impossible_links() {
+		// not adjacent
+		assert!(solve_from_adjacency(&[(0, 2)], 3).is_none());
+		// out of range
+		assert!(solve_from_adjacency(&[(8, 9)], 3).is_none());
+		// the center can't have three neighbors in the walk
+		assert!(solve_from_adjacency(&[(4, 0), (4, 2), (4, 6)], 3)
+			.is_none());
+		// a closed loop leaves no way in to the rest
+		let square = [(0, 1), (1, 4), (4, 3), (3, 0)];
+		assert!(solve_from_adjacency(&square, 3).is_none());
+	}
+}
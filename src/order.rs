@@ -0,0 +1,165 @@
+use super::State;
+
+// The order the free cells are kept in, which is the order step scans
+// swaps in and so decides which of several equally good swaps it
+// takes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum AssignmentOrder {
+	// row by row from the top left, as new finds them
+	#[default]
+	RowMajor,
+	// clockwise round the outer ring from the top left, then round
+	// each ring inside it
+	Spiral,
+	// the cells with the fewest free neighbors first, in row order
+	// on ties
+	MostConstrainedFirst,
+}
+
+impl State {
+	// Keep the free cells in order from now on, which changes the
+	// order step and the restarts try them in. A paused step_budget
+	// scan is dropped, as it counts through the old order.
+	pub fn reorder_assignments(&mut self, order: AssignmentOrder) {
+		self.order = order;
+		self.apply_order();
+	}
+	// The order the free cells are kept in
+	pub fn assignment_order(&self) -> AssignmentOrder {
+		self.order
+	}
+	// Put the free cells back in order, such as after some are freed
+	// or fixed
+	pub(crate) fn apply_order(&mut self) {
+		let assignments = std::mem::take(&mut self.assignments);
+		self.assignments = self.ordered(assignments);
+		self.scan = None;
+	}
+	// Whether the free cells are in the order kept. In row order
+	// they must also be strictly increasing, as new finds them.
+	pub(crate) fn assignments_ordered(&self) -> bool {
+		match self.order {
+			AssignmentOrder::RowMajor => self
+				.assignments
+				.windows(2)
+				.all(|pair| pair[0] < pair[1]),
+			_ => {
+				self.ordered(self.assignments.clone())
+					== self.assignments
+			}
+		}
+	}
+	// The free cells in assignments, put in the order kept
+	fn ordered(&self, mut assignments: Vec<usize>) -> Vec<usize> {
+		match self.order {
+			AssignmentOrder::RowMajor => assignments.sort_unstable(),
+			AssignmentOrder::Spiral => assignments
+				.sort_unstable_by_key(|&idx| {
+					spiral_rank(idx, self.n)
+				}),
+			AssignmentOrder::MostConstrainedFirst => {
+				let free_neighbors = |idx: usize| {
+					self.king_neighbors(idx)
+						.into_iter()
+						.filter(|x| assignments.contains(x))
+						.count()
+				};
+				let mut keyed: Vec<(usize, usize)> = assignments
+					.iter()
+					.map(|&idx| (free_neighbors(idx), idx))
+					.collect();
+				keyed.sort_unstable();
+				assignments =
+					keyed.into_iter().map(|(_, idx)| idx).collect();
+			}
+		}
+		assignments
+	}
+}
+
+// Where idx comes in a spiral over a board of side n: by ring, then
+// clockwise round the ring from its top left corner
+fn spiral_rank(idx: usize, n: usize) -> (usize, usize) {
+	let (row, col) = (idx / n, idx % n);
+	let ring = row.min(col).min(n - 1 - row).min(n - 1 - col);
+	let (first, last) = (ring, n - 1 - ring);
+	let side = last - first;
+	let along = if row == first {
+		col - first
+	} else if col == last {
+		side + row - first
+	} else if row == last {
+		2 * side + last - col
+	} else {
+		3 * side + last - row
+	};
+	(ring, along)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::seed_rng;
+
+	#[test]
+	fn reorder_assignments_should_follow_the_order() {
+		let mut state = State::new_empty(3).unwrap();
+		state.reorder_assignments(AssignmentOrder::Spiral);
+		assert_eq!(
+			state.assignments,
+			vec![0, 1, 2, 5, 8, 7, 6, 3, 4]
+		);
+		state.reorder_assignments(
+			AssignmentOrder::MostConstrainedFirst,
+		);
+		assert_eq!(
+			state.assignments,
+			vec![0, 2, 6, 8, 1, 3, 5, 7, 4]
+		);
+		state.reorder_assignments(AssignmentOrder::RowMajor);
+		assert_eq!(state.assignments, (0..9).collect::<Vec<_>>());
+		let mut state = State::new_empty(4).unwrap();
+		state.reorder_assignments(AssignmentOrder::Spiral);
+		assert_eq!(
+			state.assignments,
+			vec![
+				0, 1, 2, 3, 7, 11, 15, 14, 13, 12, 8, 4, 5, 6, 10, 9
+			]
+		);
+	}
+	#[test]
+	fn every_order_should_solve() {
+		let board =
+			vec![0, 0, 0, 0, 0, 3, 4, 0, 0, 0, 0, 0, 12, 0, 0, 0];
+		for &order in &[
+			AssignmentOrder::RowMajor,
+			AssignmentOrder::Spiral,
+			AssignmentOrder::MostConstrainedFirst,
+		] {
+			seed_rng(5);
+			let mut state = State::new(board.clone(), 4).unwrap();
+			state.reorder_assignments(order);
+			assert!(state.hillclimb());
+			assert!(state.is_solved());
+			assert_eq!(state.assignment_order(), order);
+			// the order survives fixing and freeing cells
+			assert!(state.update_clue(5, None));
+			assert!(state.assignments_ordered());
+			assert_eq!(state.to_puzzle().assignment_order(), order);
+		}
+	}
+	#[test]
+	fn row_major_should_match_the_default() {
+		let board =
+			vec![0, 0, 0, 0, 0, 3, 4, 0, 0, 0, 0, 0, 12, 0, 0, 0];
+		let mut plain = State::new(board, 4).unwrap();
+		let mut reordered = plain.clone();
+		reordered.reorder_assignments(AssignmentOrder::RowMajor);
+		assert_eq!(reordered.assignments, plain.assignments);
+		seed_rng(9);
+		assert!(plain.hillclimb());
+		seed_rng(9);
+		assert!(reordered.hillclimb());
+		assert_eq!(reordered.board, plain.board);
+	}
+}
@@ -0,0 +1,189 @@
+use super::generate::DIFFICULTY_TRIALS;
+use super::{RandomRestart, State, RNG};
+use rayon::prelude::*;
+use rayon::ThreadPoolBuilder;
+
+// Hillclimb every puzzle with up to max_restarts random restarts, on
+// a pool of threads threads (0 picks one per core), and return
+// whether each one solved along with the board it ended on, in the
+// order the puzzles were given
+pub fn solve_batch_parallel(
+	puzzles: Vec<State>,
+	threads: usize,
+	max_restarts: usize,
+) -> Vec<(bool, State)> {
+	let pool = ThreadPoolBuilder::new()
+		.num_threads(threads)
+		.build()
+		.expect("failed to start the thread pool");
+	pool.install(|| {
+		puzzles
+			.into_par_iter()
+			.map(|mut state| {
+				let solved = state.hillclimb_with_policy(
+					&RandomRestart,
+					max_restarts,
+				);
+				(solved, state)
+			})
+			.collect()
+	})
+}
+
+impl State {
+	// estimate_difficulty_seeded, with the tries spread across
+	// rayon's threads. Each try seeds its thread's generator from
+	// seed and its own number, so the estimate is the same as the
+	// serial one however many threads there are, and puts it back as
+	// it was afterwards.
+	pub fn par_estimate_difficulty(&self, seed: u64) -> f64 {
+		let total: usize = (0..DIFFICULTY_TRIALS)
+			.into_par_iter()
+			.map(|trial| {
+				let saved =
+					RNG.with(|rng_cell| rng_cell.borrow().clone());
+				let restarts = self.trial_restarts(seed, trial);
+				RNG.with(|rng_cell| *rng_cell.borrow_mut() = saved);
+				restarts
+			})
+			.sum();
+		total as f64 / DIFFICULTY_TRIALS as f64
+	}
+	// step, with the candidate swaps scored across rayon's threads.
+	// Ties go to the first in scan order, the order the free cells
+	// are kept in, so the same swap as step is made.
+	#[must_use]
+	pub fn par_step(&mut self, start_score: usize) -> usize {
+		let free = self.assignments.len();
+		let best = (0..free)
+			.into_par_iter()
+			.map_init(
+				|| self.clone(),
+				|state, first| {
+					// the best swap of the first free cell with any
+					// later one
+					let idx1 = state.assignments[first];
+					let mut best: Option<(usize, usize, usize)> =
+						None;
+					for second in first + 1..free {
+						let idx2 = state.assignments[second];
+						if !state.swap_allowed(idx1, idx2) {
+							continue;
+						}
+						state.swap(idx1, idx2);
+						let score = state.score();
+						state.swap(idx1, idx2);
+						if best.is_none_or(|(s, _, _)| score > s) {
+							best = Some((score, first, second));
+						}
+					}
+					best
+				},
+			)
+			.flatten()
+			.reduce_with(|a, b| {
+				// highest score, then first in scan order
+				if b.0 > a.0
+					|| (b.0 == a.0 && (b.1, b.2) < (a.1, a.2))
+				{
+					b
+				} else {
+					a
+				}
+			});
+		match best {
+			Some((score, first, second)) if score > start_score => {
+				self.swap(
+					self.assignments[first],
+					self.assignments[second],
+				);
+				score
+			}
+			_ => start_score,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::super::{random_puzzle, seed_rng, AssignmentOrder};
+	use super::*;
+	use rand::Rng;
+
+	#[test]
+	fn par_step_should_match_step() {
+		let orders = [
+			AssignmentOrder::RowMajor,
+			AssignmentOrder::Spiral,
+			AssignmentOrder::MostConstrainedFirst,
+		];
+		for n in 5..=8 {
+			for i in 0..12 {
+				let mut serial = random_puzzle(n, n * n / 2).unwrap();
+				serial.reorder_assignments(orders[i % 3]);
+				serial.random_start();
+				let mut parallel = serial.clone();
+				let start_score = serial.score();
+				assert_eq!(
+					serial.step(start_score),
+					parallel.par_step(start_score)
+				);
+				assert_eq!(serial.board, parallel.board);
+			}
+		}
+	}
+	#[test]
+	fn par_estimate_difficulty_should_match_the_serial_estimate() {
+		seed_rng(2);
+		let puzzle = random_puzzle(4, 10).unwrap();
+		for seed in 0..3 {
+			let serial = puzzle.estimate_difficulty_seeded(seed);
+			for threads in [1, 2, 4] {
+				let pool = ThreadPoolBuilder::new()
+					.num_threads(threads)
+					.build()
+					.unwrap();
+				let parallel = pool
+					.install(|| puzzle.par_estimate_difficulty(seed));
+				assert_eq!(serial, parallel);
+			}
+		}
+		// one thread runs every try, and its generator is put back
+		let pool =
+			ThreadPoolBuilder::new().num_threads(1).build().unwrap();
+		pool.install(|| {
+			let draw = || {
+				RNG.with(|rng_cell| {
+					rng_cell.borrow_mut().gen::<u64>()
+				})
+			};
+			seed_rng(5);
+			let expected = draw();
+			seed_rng(5);
+			puzzle.par_estimate_difficulty(0);
+			assert_eq!(draw(), expected);
+		});
+	}
+	#[test]
+	fn solve_batch_parallel_should_keep_the_order() {
+		let puzzles: Vec<State> = (0..10)
+			.map(|i| {
+				let n = 3 + i % 3;
+				random_puzzle(n, n * n / 2).unwrap()
+			})
+			.collect();
+		let results =
+			solve_batch_parallel(puzzles.clone(), 4, 10_000);
+		assert_eq!(results.len(), puzzles.len());
+		for (puzzle, (solved, state)) in puzzles.iter().zip(&results)
+		{
+			assert!(solved);
+			assert_eq!(state.score(), state.max_score());
+			// the same puzzle, now solved
+			assert_eq!(
+				state.to_puzzle_string(),
+				puzzle.to_puzzle_string()
+			);
+		}
+	}
+}
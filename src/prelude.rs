@@ -0,0 +1,8 @@
+// The items most callers need, for use kings_walk::prelude::*. Free
+// functions such as the generators stay out, so a glob import can't
+// clash with the caller's own names.
+pub use super::{
+	AssignmentOrder, Difficulty, DisplayConfig, KingsWalkError,
+	RandomRestart, RestartPolicy, ScoreLine, SolverBuilder, State,
+	StepMode, StepResult, Strategy, Topology,
+};
@@ -0,0 +1,134 @@
+use super::State;
+use image::{ImageFormat, Rgb, RgbImage};
+use std::io::Cursor;
+
+const GRID: Rgb<u8> = Rgb([64, 64, 64]);
+const FIXED: Rgb<u8> = Rgb([200, 200, 200]);
+const SOLVED: Rgb<u8> = Rgb([255, 255, 255]);
+const DIGIT: Rgb<u8> = Rgb([0, 0, 0]);
+
+// A 3x5 pixel font for the digits 0-9. Each row is 3 bits, the most
+// significant bit on the left.
+const FONT: [[u8; 5]; 10] = [
+	[0b111, 0b101, 0b101, 0b101, 0b111],
+	[0b010, 0b110, 0b010, 0b010, 0b111],
+	[0b111, 0b001, 0b111, 0b100, 0b111],
+	[0b111, 0b001, 0b111, 0b001, 0b111],
+	[0b101, 0b101, 0b111, 0b001, 0b001],
+	[0b111, 0b100, 0b111, 0b001, 0b111],
+	[0b111, 0b100, 0b111, 0b101, 0b111],
+	[0b111, 0b001, 0b010, 0b010, 0b010],
+	[0b111, 0b101, 0b111, 0b101, 0b111],
+	[0b111, 0b101, 0b111, 0b001, 0b111],
+];
+
+impl State {
+	// Draw the board as a PNG with cells cell_px pixels wide. Fixed
+	// cells are shaded grey and the cells the solver filled in are
+	// white. Returns the encoded image.
+	pub fn to_png(&self, cell_px: u32) -> Vec<u8> {
+		assert!(cell_px > 0, "a cell needs at least one pixel");
+		let side = self.n as u32 * cell_px;
+		let mut img = RgbImage::from_pixel(side, side, FIXED);
+		for &idx in &self.assignments {
+			let (x, y) = self.cell_origin(idx, cell_px);
+			for dy in 0..cell_px {
+				for dx in 0..cell_px {
+					img.put_pixel(x + dx, y + dy, SOLVED);
+				}
+			}
+		}
+		for (idx, &value) in self.board.iter().enumerate() {
+			let (x, y) = self.cell_origin(idx, cell_px);
+			draw_number(&mut img, x, y, cell_px, value);
+		}
+		// the lines between cells, and around the edge
+		for i in 0..side {
+			for j in 0..side {
+				if i % cell_px == 0 || i == side - 1 {
+					img.put_pixel(i, j, GRID);
+					img.put_pixel(j, i, GRID);
+				}
+			}
+		}
+		let mut bytes = Vec::new();
+		img.write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+			.expect("encoding to memory can't fail");
+		bytes
+	}
+	// The top left pixel of the cell at idx
+	fn cell_origin(&self, idx: usize, cell_px: u32) -> (u32, u32) {
+		let (row, col) = (idx / self.n, idx % self.n);
+		(col as u32 * cell_px, row as u32 * cell_px)
+	}
+}
+
+// Write value centered in the cell whose top left pixel is (x, y),
+// scaling the font up as far as the cell allows
+fn draw_number(
+	img: &mut RgbImage,
+	x: u32,
+	y: u32,
+	cell_px: u32,
+	value: u8,
+) {
+	let digits: Vec<usize> = value
+		.to_string()
+		.bytes()
+		.map(|b| (b - b'0') as usize)
+		.collect();
+	// each digit is 3 wide with a gap of 1 between digits
+	let text_width = digits.len() as u32 * 4 - 1;
+	let scale =
+		(cell_px * 3 / 4 / text_width).min(cell_px / 2 / 5).max(1);
+	let left = x + cell_px.saturating_sub(text_width * scale) / 2;
+	let top = y + cell_px.saturating_sub(5 * scale) / 2;
+	for (place, &digit) in digits.iter().enumerate() {
+		for (row, bits) in FONT[digit].iter().enumerate() {
+			for col in 0..3 {
+				if bits & (0b100 >> col) == 0 {
+					continue;
+				}
+				let px = left + (place as u32 * 4 + col) * scale;
+				let py = top + row as u32 * scale;
+				for dy in 0..scale {
+					for dx in 0..scale {
+						// stay inside the cell on tiny renders
+						if px + dx < x + cell_px
+							&& py + dy < y + cell_px
+						{
+							img.put_pixel(px + dx, py + dy, DIGIT);
+						}
+					}
+				}
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn to_png_should_encode_a_square_png() {
+		let mut state =
+			State::new(vec![0, 0, 1, 0, 2, 0, 9, 0, 0], 3).unwrap();
+		assert!(state.hillclimb());
+		let bytes = state.to_png(20);
+		assert!(bytes.starts_with(&[137, 80, 78, 71, 13, 10, 26, 10]));
+		let img = image::load_from_memory(&bytes).unwrap();
+		assert_eq!((img.width(), img.height()), (60, 60));
+	}
+	#[test]
+	fn to_png_should_shade_fixed_cells() {
+		let state =
+			State::new(vec![0, 0, 1, 0, 2, 0, 9, 0, 0], 3).unwrap();
+		let img = image::load_from_memory(&state.to_png(20))
+			.unwrap()
+			.to_rgb8();
+		// the top left corner inside each of the first two cells
+		assert_eq!(*img.get_pixel(1, 1), SOLVED);
+		assert_eq!(*img.get_pixel(41, 1), FIXED);
+	}
+}
@@ -0,0 +1,250 @@
+use super::{State, RNG};
+use rand::Rng;
+
+// Decides where hillclimbing starts over once it is stuck on a local
+// maximum. Implementations rearrange the free cells of the state and
+// return the new score.
+pub trait RestartPolicy {
+	fn restart(&self, state: &mut State) -> usize;
+	// The most steps to climb after the given (1-based) restart
+	// before giving up on it. None climbs to the local maximum.
+	fn step_limit(&self, _restart: usize) -> Option<usize> {
+		None
+	}
+}
+
+// Shuffle every free cell. This is the default used by hillclimb.
+pub struct RandomRestart;
+
+impl RestartPolicy for RandomRestart {
+	fn restart(&self, state: &mut State) -> usize {
+		state.random_start()
+	}
+}
+
+// Keep the current arrangement but apply k random swaps, so most of
+// the progress made so far survives the restart.
+pub struct PerturbRestart(pub usize);
+
+impl RestartPolicy for PerturbRestart {
+	fn restart(&self, state: &mut State) -> usize {
+		let free = state.assignments.len();
+		if free > 1 {
+			RNG.with(|rng_cell| {
+				let mut rng = rng_cell.borrow_mut();
+				for _ in 0..self.0 {
+					let idx1 =
+						state.assignments[rng.gen_range(0, free)];
+					let idx2 =
+						state.assignments[rng.gen_range(0, free)];
+					if state.swap_allowed(idx1, idx2) {
+						state.swap(idx1, idx2);
+					}
+				}
+			});
+		}
+		state.score()
+	}
+}
+
+// Shuffle, then walk the free cells in order and give each one the
+// remaining value that links it to the most of its neighbors.
+pub struct GreedyRestart;
+
+impl RestartPolicy for GreedyRestart {
+	fn restart(&self, state: &mut State) -> usize {
+		// the shuffle decides how ties are broken
+		state.random_start();
+		for first in 0..state.assignments.len() {
+			let idx1 = state.assignments[first];
+			let mut best = (state.links_at(idx1), idx1);
+			for next in first + 1..state.assignments.len() {
+				let idx2 = state.assignments[next];
+				if !state.swap_allowed(idx1, idx2) {
+					continue;
+				}
+				state.swap(idx1, idx2);
+				let links = state.links_at(idx1);
+				if links > best.0 {
+					best = (links, idx2);
+				}
+				state.swap(idx1, idx2);
+			}
+			state.swap(idx1, best.1);
+		}
+		state.score()
+	}
+}
+
+impl State {
+	// Shuffle the values of the free cells that are missing a link
+	// to the value before or after them, leaving every free cell
+	// whose links are all made alone, and return the new score. With
+	// fewer than two such cells there is nothing to shuffle, so
+	// every free cell is shuffled as random_start does. The links
+	// kept may belong to no solution at all, so climbing only ever
+	// from biased restarts can get stuck.
+	pub fn biased_restart(&mut self) -> usize {
+		let defects: Vec<usize> = self
+			.assignments
+			.iter()
+			.copied()
+			.filter(|&idx| self.in_defect(idx))
+			.collect();
+		if defects.len() < 2 {
+			return self.random_start();
+		}
+		RNG.with(|rng_cell| {
+			let mut rng = rng_cell.borrow_mut();
+			for i in (1..defects.len()).rev() {
+				let j = rng.gen_range(0, i + 1);
+				self.swap(defects[i], defects[j]);
+			}
+		});
+		self.meet_restrictions();
+		self.score()
+	}
+	// Whether the value at idx is not next to a value the walk needs
+	// beside it
+	fn in_defect(&self, idx: usize) -> bool {
+		let v = self.board[idx] as usize;
+		let linked =
+			|w: usize| self.king_distance(idx, self.pos[w]) == 1;
+		(v > 1 && self.unbroken(v - 1, v) && !linked(v - 1))
+			|| (v < self.board.len()
+				&& self.unbroken(v, v + 1)
+				&& !linked(v + 1))
+	}
+}
+
+// Shuffle every free cell, but cut each climb short after
+// unit * luby(restart) steps. Short runs are tried often and long
+// runs rarely, which is within a constant factor of the optimal
+// schedule when nothing is known about the puzzle.
+pub struct LubyRestart {
+	pub unit: usize,
+}
+
+impl RestartPolicy for LubyRestart {
+	fn restart(&self, state: &mut State) -> usize {
+		state.random_start()
+	}
+	fn step_limit(&self, restart: usize) -> Option<usize> {
+		Some(self.unit.saturating_mul(luby(restart)))
+	}
+}
+
+// The i-th (1-based) term of the Luby sequence:
+// 1, 1, 2, 1, 1, 2, 4, 1, 1, 2, 1, 1, 2, 4, 8, ...
+pub fn luby(mut i: usize) -> usize {
+	assert!(i > 0, "the Luby sequence starts at 1");
+	loop {
+		// find the smallest k such that i <= 2^k - 1
+		let mut k = 1;
+		while (1 << k) - 1 < i {
+			k += 1;
+		}
+		// at the end of a block the term is 2^(k-1), otherwise the
+		// sequence repeats itself from the start
+		if i == (1 << k) - 1 {
+			return 1 << (k - 1);
+		}
+		i -= (1 << (k - 1)) - 1;
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::super::seed_rng;
+	use super::*;
+
+	fn solves(policy: &dyn RestartPolicy) {
+		for (board, n) in [
+			(vec![0, 0, 1, 0, 2, 0, 9, 0, 0], 3),
+			(
+				vec![0, 0, 0, 0, 0, 3, 4, 0, 0, 0, 0, 0, 12, 0, 0, 0],
+				4,
+			),
+		] {
+			let mut state = State::new(board, n).unwrap();
+			assert!(state.hillclimb_with_policy(policy, 10_000));
+			assert_eq!(state.score(), state.max_score());
+		}
+	}
+	#[test]
+	fn random_restart_should_solve() {
+		solves(&RandomRestart);
+	}
+	#[test]
+	fn perturb_restart_should_solve() {
+		solves(&PerturbRestart(3));
+	}
+	#[test]
+	fn greedy_restart_should_solve() {
+		solves(&GreedyRestart);
+	}
+	#[test]
+	fn biased_restart_should_keep_more_of_a_near_solved_board() {
+		// a snake through the empty 5x5 board with two values swapped
+		let mut near_solved = State::new_empty(5).unwrap();
+		#[rustfmt::skip]
+		near_solved.set_board(vec![
+			 1,  2,  3,  4,  5,
+			10,  9,  8,  7,  6,
+			11, 12, 13, 14, 15,
+			20, 19, 18, 17, 16,
+			21, 22, 23, 25, 24,
+		]);
+		seed_rng(155);
+		let (mut biased, mut random) = (0, 0);
+		for _ in 0..100 {
+			biased += near_solved.clone().biased_restart();
+			random += near_solved.clone().random_start();
+		}
+		assert!(biased > random, "{} <= {}", biased, random);
+	}
+	#[test]
+	fn luby_restart_should_solve() {
+		solves(&LubyRestart { unit: 2 });
+	}
+	#[test]
+	fn luby_restart_should_solve_n_eq_6() {
+		#[rustfmt::skip]
+		let mut state = State::new(vec![
+			 0,  0,  0,  0,  2,  1,
+			 0,  0,  0,  0, 11, 12,
+			 0,  0,  0,  0, 14, 13,
+			 0,  0,  0,  0, 23, 24,
+			30, 29, 28, 27, 26, 25,
+			31, 32, 33, 34, 35, 36,
+		], 6).unwrap();
+		assert!(state.hillclimb_with_policy(
+			&LubyRestart { unit: 8 },
+			100_000
+		));
+		assert_eq!(state.score(), state.max_score());
+	}
+	#[test]
+	fn luby_should_match_the_sequence() {
+		let expected =
+			vec![1, 1, 2, 1, 1, 2, 4, 1, 1, 2, 1, 1, 2, 4, 8, 1];
+		let actual: Vec<usize> = (1..=16).map(luby).collect();
+		assert_eq!(expected, actual);
+	}
+	#[test]
+	fn hillclimb_with_policy_should_give_up() {
+		// 1 and 2 are fixed in opposite corners
+		let mut state =
+			State::new(vec![1, 0, 0, 0, 0, 0, 0, 0, 2], 3).unwrap();
+		assert!(!state.hillclimb_with_policy(&RandomRestart, 5));
+	}
+	#[test]
+	fn hillclimb_with_policy_should_keep_the_best_board() {
+		// 1 and 2 are fixed in opposite corners, but every other
+		// link can be made
+		let mut state =
+			State::new(vec![1, 0, 0, 0, 0, 0, 0, 0, 2], 3).unwrap();
+		assert!(!state.hillclimb_with_policy(&PerturbRestart(1), 200));
+		assert_eq!(state.score(), state.max_score() - 1);
+	}
+}
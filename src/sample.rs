@@ -0,0 +1,44 @@
+use super::{seed_rng, KingsWalkError, State};
+use std::collections::BTreeMap;
+
+// Climb from attempts random starts of the puzzle, each to its local
+// maximum, and count how many attempts finished on each score. The
+// seed makes the sampling reproducible.
+pub fn score_histogram(
+	board: Vec<u8>,
+	n: usize,
+	attempts: usize,
+	seed: u64,
+) -> Result<BTreeMap<usize, usize>, KingsWalkError> {
+	let mut state = State::new(board, n)?;
+	seed_rng(seed);
+	let mut histogram = BTreeMap::new();
+	for _ in 0..attempts {
+		let start_score = state.random_start();
+		let score = state.climb(start_score, usize::MAX);
+		*histogram.entry(score).or_insert(0) += 1;
+	}
+	Ok(histogram)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn score_histogram_should_count_every_attempt() {
+		let board = vec![0, 0, 1, 0, 2, 0, 9, 0, 0];
+		let histogram = score_histogram(board, 3, 200, 7).unwrap();
+		// some attempt solves the board, and none can do better
+		assert_eq!(histogram.keys().last(), Some(&8));
+		assert_eq!(histogram.values().sum::<usize>(), 200);
+	}
+	#[test]
+	fn score_histogram_should_be_reproducible() {
+		let board = vec![0, 0, 1, 0, 2, 0, 9, 0, 0];
+		assert_eq!(
+			score_histogram(board.clone(), 3, 50, 1),
+			score_histogram(board, 3, 50, 1)
+		);
+	}
+}
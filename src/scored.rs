@@ -0,0 +1,93 @@
+use super::State;
+use std::cmp::Ordering;
+
+// A state together with its score, ordered by score and then by board
+// so that ties still come out in the same order every time. State
+// itself has no ordering, as there is no one obvious way to order
+// puzzles.
+#[derive(Debug, Clone)]
+pub struct ScoredState {
+	score: usize,
+	state: State,
+}
+
+impl ScoredState {
+	pub fn new(state: State) -> ScoredState {
+		ScoredState {
+			score: state.score(),
+			state,
+		}
+	}
+	// The score of the state, as it was when wrapped
+	pub fn score(&self) -> usize {
+		self.score
+	}
+	pub fn state(&self) -> &State {
+		&self.state
+	}
+	pub fn into_state(self) -> State {
+		self.state
+	}
+}
+
+impl PartialEq for ScoredState {
+	fn eq(&self, other: &ScoredState) -> bool {
+		self.cmp(other) == Ordering::Equal
+	}
+}
+
+impl Eq for ScoredState {}
+
+impl PartialOrd for ScoredState {
+	fn partial_cmp(&self, other: &ScoredState) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+impl Ord for ScoredState {
+	fn cmp(&self, other: &ScoredState) -> Ordering {
+		self.score
+			.cmp(&other.score)
+			.then_with(|| self.state.board.cmp(&other.state.board))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::collections::BinaryHeap;
+
+	#[test]
+	fn scored_states_should_pop_best_first() {
+		let mut heap = BinaryHeap::new();
+		for board in [
+			vec![1, 2, 3, 4, 5, 6, 7, 8, 9],
+			vec![1, 2, 3, 6, 5, 4, 7, 8, 9],
+			vec![9, 1, 5, 2, 6, 3, 7, 4, 8],
+			vec![3, 4, 1, 5, 2, 6, 9, 7, 8],
+		] {
+			heap.push(ScoredState::new(
+				State::new(board, 3).unwrap(),
+			));
+		}
+		let mut scores = Vec::new();
+		while let Some(scored) = heap.pop() {
+			assert_eq!(scored.score(), scored.state().score());
+			scores.push(scored.score());
+		}
+		assert_eq!(scores.len(), 4);
+		assert!(scores.windows(2).all(|pair| pair[0] >= pair[1]));
+		assert_eq!(scores[0], 8);
+	}
+	#[test]
+	fn scored_states_should_break_ties_by_board() {
+		let a =
+			State::new(vec![1, 2, 3, 4, 5, 6, 7, 8, 9], 3).unwrap();
+		let b =
+			State::new(vec![1, 2, 3, 4, 5, 6, 8, 7, 9], 3).unwrap();
+		assert_eq!(a.score(), b.score());
+		let (a, b) = (ScoredState::new(a), ScoredState::new(b));
+		assert!(a < b);
+		assert_eq!(a.clone(), a);
+	}
+}
@@ -0,0 +1,149 @@
+use super::State;
+
+impl State {
+	// Where the cell at idx ends up under the t-th (0..8) symmetry of
+	// the square: a mirror left to right when t >= 4, then t % 4
+	// quarter turns clockwise
+	fn transform_index(&self, idx: usize, t: usize) -> usize {
+		let n = self.n;
+		let (mut row, mut col) = (idx / n, idx % n);
+		if t >= 4 {
+			col = n - 1 - col;
+		}
+		for _ in 0..t % 4 {
+			let turned = (col, n - 1 - row);
+			row = turned.0;
+			col = turned.1;
+		}
+		row * n + col
+	}
+	// A copy of the state under the t-th symmetry, with the free
+	// cells moving along with their values
+	pub(crate) fn transformed(&self, t: usize) -> State {
+		let mut board = vec![0; self.board.len()];
+		for (idx, &value) in self.board.iter().enumerate() {
+			board[self.transform_index(idx, t)] = value;
+		}
+		let assignments: Vec<usize> = self
+			.assignments
+			.iter()
+			.map(|&idx| self.transform_index(idx, t))
+			.collect();
+		let mut state = self.clone();
+		state.assignments = assignments;
+		state.restrictions = self
+			.restrictions
+			.iter()
+			.map(|(&idx, allowed)| {
+				(self.transform_index(idx, t), allowed.clone())
+			})
+			.collect();
+		state.set_board(board);
+		state.apply_order();
+		state
+	}
+	// The state turned a quarter turn clockwise
+	pub fn rotated(&self) -> State {
+		self.transformed(1)
+	}
+	// The state mirrored left to right
+	pub fn reflected(&self) -> State {
+		self.transformed(4)
+	}
+	// How many of the 8 symmetries of the square leave the puzzle,
+	// its clues with the free cells blank, unchanged. 1 means it has
+	// no symmetry and 8 that it has them all.
+	pub fn symmetry_order(&self) -> usize {
+		let original = self.clue_board();
+		(0..8)
+			.filter(|&t| self.transformed(t).clue_board() == original)
+			.count()
+	}
+	// The distinct puzzles, clues with 0 for the free cells, that the
+	// 8 symmetries of the square turn this one into, itself included,
+	// sorted. There are 8 / symmetry_order of them, and two puzzles
+	// are the same up to symmetry exactly when these match.
+	pub fn equivalence_class(&self) -> Vec<Vec<u8>> {
+		let mut class: Vec<Vec<u8>> = (0..8)
+			.map(|t| self.transformed(t).clue_board())
+			.collect();
+		class.sort_unstable();
+		class.dedup();
+		class
+	}
+	// The board with 0 in every free cell
+	fn clue_board(&self) -> Vec<u8> {
+		let mut board = self.board.clone();
+		for &idx in &self.assignments {
+			board[idx] = 0;
+		}
+		board
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn rotated_should_turn_clockwise() {
+		// 3 4 1    9 8 3
+		// 8 2 5 -> 7 2 4
+		// 9 7 6    6 5 1
+		let state =
+			State::new(vec![3, 4, 1, 8, 2, 5, 9, 7, 6], 3).unwrap();
+		let rotated = state.rotated();
+		assert_eq!(rotated.board, vec![9, 8, 3, 7, 2, 4, 6, 5, 1]);
+		assert_eq!(rotated.score(), rotated.max_score());
+		let reflected = state.reflected();
+		assert_eq!(reflected.board, vec![1, 4, 3, 5, 2, 8, 6, 7, 9]);
+		// four turns come back around
+		let turned = rotated.rotated().rotated().rotated();
+		assert_eq!(turned.board, state.board);
+	}
+	#[test]
+	fn equivalence_class_should_hold_every_distinct_transform() {
+		// a fully given solution with no symmetry
+		let state =
+			State::new(vec![3, 4, 1, 8, 2, 5, 9, 7, 6], 3).unwrap();
+		let class = state.equivalence_class();
+		assert_eq!(class.len(), 8);
+		assert!(class.contains(&state.board));
+		assert!(class.contains(&state.rotated().board));
+		// every member has the same class
+		for board in &class {
+			let other = State::new(board.clone(), 3).unwrap();
+			assert_eq!(other.equivalence_class(), class);
+		}
+		// mirrored along the main diagonal
+		let state =
+			State::new(vec![1, 0, 0, 0, 0, 0, 0, 0, 9], 3).unwrap();
+		assert_eq!(
+			state.equivalence_class(),
+			vec![
+				vec![0, 0, 1, 0, 0, 0, 9, 0, 0],
+				vec![0, 0, 9, 0, 0, 0, 1, 0, 0],
+				vec![1, 0, 0, 0, 0, 0, 0, 0, 9],
+				vec![9, 0, 0, 0, 0, 0, 0, 0, 1],
+			]
+		);
+		let state =
+			State::new(vec![0, 0, 0, 0, 5, 0, 0, 0, 0], 3).unwrap();
+		assert_eq!(state.equivalence_class().len(), 1);
+	}
+	#[test]
+	fn symmetry_order_should_count_symmetries() {
+		// only the center is a clue
+		let state =
+			State::new(vec![0, 0, 0, 0, 5, 0, 0, 0, 0], 3).unwrap();
+		assert_eq!(state.symmetry_order(), 8);
+		// the corners mirror along the main diagonal
+		let state =
+			State::new(vec![1, 0, 0, 0, 0, 0, 0, 0, 9], 3).unwrap();
+		assert_eq!(state.symmetry_order(), 2);
+		// a fully given solution
+		let state =
+			State::new(vec![3, 4, 1, 8, 2, 5, 9, 7, 6], 3).unwrap();
+		assert_eq!(state.symmetry_order(), 1);
+	}
+}
@@ -0,0 +1,170 @@
+use super::{KingsWalkError, State};
+
+// Which cells count as neighbors, so which steps the walk may take.
+// Cells are numbered row by row either way, so (row, col) is the cell
+// row * n + col.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Topology {
+	// a square grid, where each cell touches the (up to) 8 around
+	// it as a king moves
+	#[default]
+	Square,
+	// a grid of hexagons in rows, with every odd row pushed half a
+	// cell to the right, where each cell touches (up to) 6: the two
+	// beside it and two each in the rows above and below. rotated,
+	// reflected and symmetry_order still use the symmetries of the
+	// square, which don't carry over.
+	HexOffset,
+}
+
+impl Topology {
+	// The cells next to idx on a board of side n
+	pub(crate) fn neighbors(
+		self,
+		idx: usize,
+		n: usize,
+	) -> Vec<usize> {
+		let (row, col) = (idx / n, idx % n);
+		let (row, col) = (row as isize, col as isize);
+		let offsets: &[(isize, isize)] = match self {
+			Topology::Square => &[
+				(-1, -1),
+				(-1, 0),
+				(-1, 1),
+				(0, -1),
+				(0, 1),
+				(1, -1),
+				(1, 0),
+				(1, 1),
+			],
+			// the rows above and below reach back a cell on even rows
+			// and forward a cell on odd ones
+			Topology::HexOffset if row % 2 == 0 => {
+				&[(-1, -1), (-1, 0), (0, -1), (0, 1), (1, -1), (1, 0)]
+			}
+			Topology::HexOffset => {
+				&[(-1, 0), (-1, 1), (0, -1), (0, 1), (1, 0), (1, 1)]
+			}
+		};
+		offsets
+			.iter()
+			.map(|&(dr, dc)| (row + dr, col + dc))
+			.filter(|&(r, c)| {
+				(0..n as isize).contains(&r)
+					&& (0..n as isize).contains(&c)
+			})
+			.map(|(r, c)| r as usize * n + c as usize)
+			.collect()
+	}
+	// The fewest steps between neighbors from idx1 to idx2 on a board
+	// of side n
+	pub(crate) fn distance(
+		self,
+		idx1: usize,
+		idx2: usize,
+		n: usize,
+	) -> usize {
+		let (r1, c1) = (idx1 / n, idx1 % n);
+		let (r2, c2) = (idx2 / n, idx2 % n);
+		match self {
+			Topology::Square => r1.abs_diff(r2).max(c1.abs_diff(c2)),
+			Topology::HexOffset => {
+				// in cube coordinates (q, r, -q-r) the distance is
+				// the largest change in any of the three
+				let cube = |row: usize, col: usize| {
+					let q = col as isize
+						- (row as isize - (row & 1) as isize) / 2;
+					(q, row as isize)
+				};
+				let (q1, s1) = cube(r1, c1);
+				let (q2, s2) = cube(r2, c2);
+				let (dq, dr) = (q1 - q2, s1 - s2);
+				dq.unsigned_abs()
+					.max(dr.unsigned_abs())
+					.max((dq + dr).unsigned_abs())
+			}
+		}
+	}
+}
+
+impl State {
+	// new, on a board laid out as topology says
+	pub fn with_topology(
+		board: Vec<u8>,
+		n: usize,
+		topology: Topology,
+	) -> Result<State, KingsWalkError> {
+		let mut state = State::new(board, n)?;
+		state.topology = topology;
+		Ok(state)
+	}
+	// How the cells of the board are laid out
+	pub fn topology(&self) -> Topology {
+		self.topology
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn hex_offset_should_have_six_neighbors() {
+		let hex = Topology::HexOffset;
+		// 0 1 2
+		//  3 4 5
+		// 6 7 8
+		assert_eq!(hex.neighbors(0, 3), vec![1, 3]);
+		assert_eq!(hex.neighbors(2, 3), vec![1, 4, 5]);
+		assert_eq!(hex.neighbors(4, 3), vec![1, 2, 3, 5, 7, 8]);
+		assert_eq!(hex.neighbors(3, 3), vec![0, 1, 4, 6, 7]);
+		assert_eq!(hex.neighbors(7, 3), vec![3, 4, 6, 8]);
+		// the distance is 1 exactly between neighbors
+		for idx1 in 0..16 {
+			for idx2 in 0..16 {
+				assert_eq!(
+					hex.distance(idx1, idx2, 4) == 1,
+					hex.neighbors(idx1, 4).contains(&idx2)
+				);
+			}
+		}
+		assert_eq!(hex.distance(0, 15, 4), 5);
+		assert_eq!(Topology::Square.distance(0, 15, 4), 3);
+	}
+	#[test]
+	fn hex_offset_should_solve() {
+		// 1 3 4
+		//  9 2 5
+		// 8 7 6 is a walk on a square but not on hexagons, as 1 and 2
+		// aren't neighbors there
+		let board = vec![1, 3, 4, 9, 2, 5, 8, 7, 6];
+		let square = State::new(board.clone(), 3).unwrap();
+		let hex = State::with_topology(board, 3, Topology::HexOffset)
+			.unwrap();
+		assert!(square.is_solved());
+		assert!(!hex.is_solved());
+		assert_eq!(hex.score(), 7);
+		assert_eq!(hex.max_score(), 8);
+		for solve in [
+			State::solve_exact,
+			State::solve_hamiltonian,
+			State::hillclimb,
+		] {
+			let mut state = State::with_topology(
+				vec![0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+				4,
+				Topology::HexOffset,
+			)
+			.unwrap();
+			assert!(solve(&mut state));
+			assert!(state.is_solved());
+			assert_eq!(state.topology(), Topology::HexOffset);
+			let path = state.solution_path();
+			assert!(path.windows(2).all(|pair| {
+				Topology::HexOffset
+					.neighbors(pair[0], 4)
+					.contains(&pair[1])
+			}));
+		}
+	}
+}
@@ -0,0 +1,69 @@
+// Run the kings-walk binary on puzzles fed through stdin
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+fn run_cli(input: &str) -> Output {
+	let mut child = Command::new(env!("CARGO_BIN_EXE_kings-walk"))
+		.stdin(Stdio::piped())
+		.stdout(Stdio::piped())
+		.stderr(Stdio::piped())
+		.spawn()
+		.unwrap();
+	child
+		.stdin
+		.take()
+		.unwrap()
+		.write_all(input.as_bytes())
+		.unwrap();
+	child.wait_with_output().unwrap()
+}
+
+#[test]
+fn cli_should_solve_a_puzzle() {
+	let output = run_cli("0 0 1\n0 2 0\n9 0 0\n");
+	assert!(output.status.success());
+	let stdout = String::from_utf8(output.stdout).unwrap();
+	assert!(stdout.ends_with("score: 8\n"), "{}", stdout);
+}
+
+#[test]
+fn cli_should_name_a_duplicate_value() {
+	let output = run_cli("0 0 1\n0 2 0\n9 0 2\n");
+	assert!(!output.status.success());
+	let stderr = String::from_utf8(output.stderr).unwrap();
+	assert_eq!(
+		stderr,
+		"error: the value 2 at row 3, column 3 appears more than \
+		 once\n"
+	);
+}
+
+#[test]
+fn cli_should_name_bad_cells() {
+	let output = run_cli("0 0 1\n0 12 0\n9 0 0\n");
+	assert!(!output.status.success());
+	let stderr = String::from_utf8(output.stderr).unwrap();
+	assert_eq!(
+		stderr,
+		"error: the value 12 at row 2, column 2 is not in [1,9]\n"
+	);
+	let output = run_cli("0 0 1\n0 two 0\n9 0 0\n");
+	let stderr = String::from_utf8(output.stderr).unwrap();
+	assert_eq!(
+		stderr,
+		"error: \"two\" at row 2, column 2 is not a number\n"
+	);
+}
+
+#[test]
+fn cli_should_report_ragged_rows() {
+	for input in ["0 0 0 0 x\n0\n0\n", "0 0 0 x\n0 0 0\n0 0 0"] {
+		let output = run_cli(input);
+		assert_eq!(output.status.code(), Some(2));
+		let stderr = String::from_utf8(output.stderr).unwrap();
+		assert_eq!(
+			stderr,
+			"error: a board of 3 rows needs 3 cells in every row\n"
+		);
+	}
+}
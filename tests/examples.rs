@@ -0,0 +1,33 @@
+// Build and run the example programs end to end
+use std::process::{Command, Output};
+
+fn run_example(args: &[&str]) -> Output {
+	let output = Command::new(env!("CARGO"))
+		.args(["run", "--quiet", "--example"])
+		.args(args)
+		.current_dir(env!("CARGO_MANIFEST_DIR"))
+		.output()
+		.unwrap();
+	assert!(
+		output.status.success(),
+		"{}",
+		String::from_utf8_lossy(&output.stderr)
+	);
+	output
+}
+
+#[test]
+fn solve_file_should_print_a_solution() {
+	let output =
+		run_example(&["solve_file", "--", "examples/n_eq_3.kw"]);
+	let stdout = String::from_utf8(output.stdout).unwrap();
+	assert!(stdout.ends_with("score: 8\n"), "{}", stdout);
+}
+
+#[test]
+fn generate_should_print_a_puzzle() {
+	let output = run_example(&["generate", "--", "4", "6", "1"]);
+	let stdout = String::from_utf8(output.stdout).unwrap();
+	assert_eq!(stdout.lines().count(), 4);
+	assert_eq!(stdout.matches('.').count(), 6);
+}
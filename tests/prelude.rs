@@ -0,0 +1,35 @@
+// Use the crate the way a downstream crate would, through the prelude
+// alone
+use kings_walk::prelude::*;
+
+fn solve(text: &str) -> Result<State, KingsWalkError> {
+	let mut state: State = text.parse()?;
+	assert!(state.hillclimb_with_policy(&RandomRestart, 1_000));
+	Ok(state)
+}
+
+#[test]
+fn prelude_should_cover_a_typical_caller() {
+	let state = solve("0 0 1\n0 2 0\n9 0 0\n").unwrap();
+	let config = DisplayConfig {
+		score_line: ScoreLine::ScoreOfMax,
+	};
+	assert!(state
+		.display(config)
+		.to_string()
+		.ends_with("score: 8/8\n"));
+	assert!(state.classify() < Difficulty::Hard);
+	let mut copy = state.clone();
+	let score = copy.score();
+	assert_eq!(
+		copy.step_with_mode(score, StepMode::FirstImprovement),
+		8
+	);
+	assert_eq!(copy.step_budget(usize::MAX), StepResult::Done(8));
+	assert_eq!(
+		solve("1 0 0\n0 0 0\n0 0 1\n").unwrap_err(),
+		KingsWalkError::DuplicateValue(8)
+	);
+	let _ = Strategy::Auto;
+	let _: &dyn RestartPolicy = &RandomRestart;
+}
@@ -0,0 +1,47 @@
+// Snapshots of the text renderings of solved boards, so any change
+// to their layout shows up in review
+use kings_walk::State;
+
+fn solved(board: Vec<u8>, n: usize) -> State {
+	let mut state = State::new(board, n).unwrap();
+	assert!(state.solve_exact());
+	state
+}
+
+fn solved_3() -> State {
+	solved(vec![0, 0, 1, 0, 2, 0, 9, 0, 0], 3)
+}
+
+fn solved_4() -> State {
+	solved(vec![1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 12, 0, 0, 0, 0, 16], 4)
+}
+
+#[test]
+fn display_3() {
+	insta::assert_snapshot!(solved_3().to_string());
+}
+
+#[test]
+fn display_4() {
+	insta::assert_snapshot!(solved_4().to_string());
+}
+
+#[test]
+fn render_arrows_3() {
+	insta::assert_snapshot!(solved_3().render_arrows());
+}
+
+#[test]
+fn render_arrows_4() {
+	insta::assert_snapshot!(solved_4().render_arrows());
+}
+
+#[test]
+fn to_puzzle_string_3() {
+	insta::assert_snapshot!(solved_3().to_puzzle_string());
+}
+
+#[test]
+fn to_puzzle_string_4() {
+	insta::assert_snapshot!(solved_4().to_puzzle_string());
+}
@@ -0,0 +1,43 @@
+// Drive the kw-repl binary with a script of commands
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+fn run_repl(script: &str) -> String {
+	let mut child = Command::new(env!("CARGO_BIN_EXE_kw-repl"))
+		.stdin(Stdio::piped())
+		.stdout(Stdio::piped())
+		.spawn()
+		.unwrap();
+	child
+		.stdin
+		.take()
+		.unwrap()
+		.write_all(script.as_bytes())
+		.unwrap();
+	let output = child.wait_with_output().unwrap();
+	assert!(output.status.success());
+	String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn repl_should_solve_a_board() {
+	let stdout = run_repl(
+		"new 3\nset 0 0 9\nhint\nscore\nundo\nset 1 1 \
+		 5\nsolve\nshow\n",
+	);
+	assert!(stdout.contains("swap ("), "{}", stdout);
+	assert!(stdout.ends_with("score: 8\n"), "{}", stdout);
+}
+
+#[test]
+fn repl_should_report_bad_commands() {
+	let stdout =
+		run_repl("show\nset 0 0 1\nnew 3\nset 3 0 1\nfly\nundo\n");
+	let lines: Vec<&str> = stdout.lines().collect();
+	assert_eq!(lines[0], "no board, use new <n>");
+	assert_eq!(lines[1], "no board, use new <n>");
+	assert!(stdout.contains("(3, 0) is not on the board."));
+	assert!(stdout.contains("unknown command: fly"));
+	// undoing new goes back to no board
+	assert_eq!(lines.last(), Some(&"no board, use new <n>"));
+}